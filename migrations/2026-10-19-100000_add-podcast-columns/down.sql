@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE letters DROP reading_order;
+ALTER TABLE letters DROP featured;
\ No newline at end of file
@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER TABLE letters ADD COLUMN featured BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE letters ADD COLUMN reading_order INTEGER;
\ No newline at end of file
@@ -0,0 +1,8 @@
+-- Your SQL goes here
+CREATE TABLE votes (
+    letter_id INTEGER NOT NULL REFERENCES letters(id),
+    moderator_id VARCHAR NOT NULL,
+    value INTEGER NOT NULL,
+
+    PRIMARY KEY (letter_id, moderator_id)
+)
\ No newline at end of file
@@ -0,0 +1,6 @@
+-- Your SQL goes here
+CREATE TABLE blocked_users (
+    user_id VARCHAR NOT NULL PRIMARY KEY,
+    reason VARCHAR NOT NULL,
+    blocked_by VARCHAR NOT NULL
+)
\ No newline at end of file
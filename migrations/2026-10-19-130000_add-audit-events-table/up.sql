@@ -0,0 +1,11 @@
+CREATE TABLE audit_events (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    action VARCHAR NOT NULL,
+    actor_id VARCHAR NOT NULL,
+    target VARCHAR,
+    details VARCHAR,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX audit_events_action ON audit_events (action);
+CREATE INDEX audit_events_actor_id ON audit_events (actor_id)
\ No newline at end of file
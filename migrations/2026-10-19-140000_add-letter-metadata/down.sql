@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE letters DROP channel_id;
+ALTER TABLE letters DROP guild_id;
+ALTER TABLE letters DROP updated_at;
+ALTER TABLE letters DROP created_at;
\ No newline at end of file
@@ -0,0 +1,12 @@
+-- Your SQL goes here
+ALTER TABLE letters ADD COLUMN created_at TIMESTAMP;
+ALTER TABLE letters ADD COLUMN updated_at TIMESTAMP;
+ALTER TABLE letters ADD COLUMN guild_id VARCHAR;
+ALTER TABLE letters ADD COLUMN channel_id VARCHAR;
+
+-- letters logged to the audit channel were sent when their audit message was,
+-- which the message ID tells: milliseconds since 2015 in its upper 42 bits
+UPDATE letters
+SET created_at = datetime(((CAST(message_id AS INTEGER) >> 22) + 1420070400000) / 1000, 'unixepoch'),
+    updated_at = datetime(((CAST(message_id AS INTEGER) >> 22) + 1420070400000) / 1000, 'unixepoch')
+WHERE message_id IS NOT NULL;
\ No newline at end of file
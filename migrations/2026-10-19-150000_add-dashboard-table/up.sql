@@ -0,0 +1,7 @@
+-- Your SQL goes here
+-- The pinned statistics message, which only ever has one row
+CREATE TABLE dashboard (
+    id INTEGER NOT NULL PRIMARY KEY CHECK (id = 1),
+    channel_id VARCHAR NOT NULL,
+    message_id VARCHAR NOT NULL
+)
\ No newline at end of file
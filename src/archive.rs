@@ -0,0 +1,194 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+use ellipse::Ellipse;
+
+use crate::model::Letter;
+
+const TITLE: &str = "2023 Classroom of the Elite Valentine's Event";
+
+const STYLE: &str = "\
+body {
+  margin: 0;
+  background: #fff0f5;
+  color: #3b1f2b;
+  font-family: Georgia, 'Times New Roman', serif;
+  line-height: 1.6;
+}
+main {
+  max-width: 42rem;
+  margin: 0 auto;
+  padding: 2rem 1rem;
+}
+h1, h2 {
+  color: #c71f5d;
+}
+a {
+  color: #c71f5d;
+}
+ul {
+  list-style: none;
+  padding: 0;
+}
+li, article {
+  background: #ffffff;
+  border-left: 4px solid #ff69b4;
+  border-radius: 4px;
+  margin: 0.75rem 0;
+  padding: 0.75rem 1rem;
+}
+.letter {
+  white-space: pre-wrap;
+}
+footer {
+  color: #9e5a76;
+  font-size: 0.875rem;
+  text-align: center;
+  padding: 2rem 0;
+}
+";
+
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Turns a recipient into an HTML id for linking to their section.
+fn anchor(recipient: &str) -> String {
+    recipient
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+fn page(title: &str, stylesheet: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>
+<html lang=\"en\">
+<head>
+<meta charset=\"utf-8\">
+<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">
+<title>{}</title>
+<link rel=\"stylesheet\" href=\"{stylesheet}\">
+</head>
+<body>
+<main>
+{body}</main>
+<footer>{TITLE}</footer>
+</body>
+</html>
+",
+        escape(title)
+    )
+}
+
+/// Only letters that were not sent anonymously are signed.
+fn signature(letter: &Letter) -> String {
+    if letter.anon {
+        "Anonymous".to_owned()
+    } else {
+        escape(&letter.sender)
+    }
+}
+
+fn index_page(letters: &[Letter]) -> String {
+    let mut by_recipient: BTreeMap<&str, Vec<&Letter>> = BTreeMap::new();
+    for letter in letters {
+        by_recipient
+            .entry(&letter.recipient)
+            .or_default()
+            .push(letter);
+    }
+
+    let mut body = format!("<h1>{}</h1>\n<nav>\n<ul>\n", escape(TITLE));
+    for (recipient, letters) in &by_recipient {
+        let _ = writeln!(
+            body,
+            "<li><a href=\"#{}\">{}</a> ({} letter{})</li>",
+            anchor(recipient),
+            escape(recipient),
+            letters.len(),
+            if letters.len() == 1 { "" } else { "s" }
+        );
+    }
+    body.push_str("</ul>\n</nav>\n");
+
+    for (recipient, letters) in &by_recipient {
+        let _ = write!(
+            body,
+            "<section id=\"{}\">\n<h2>To {}</h2>\n<ul>\n",
+            anchor(recipient),
+            escape(recipient)
+        );
+        for letter in letters {
+            let _ = writeln!(
+                body,
+                "<li><a href=\"letters/{}.html\">From {}</a>: {}</li>",
+                letter.id,
+                signature(letter),
+                escape(letter.content.as_str().truncate_ellipse(80).as_ref())
+            );
+        }
+        body.push_str("</ul>\n</section>\n");
+    }
+
+    page(TITLE, "style.css", &body)
+}
+
+fn letter_page(letter: &Letter) -> String {
+    let body = format!(
+        "<p><a href=\"../index.html#{}\">Back to all letters</a></p>
+<article>
+<h2>To {}</h2>
+<p class=\"letter\">{}</p>
+<p>From {}</p>
+</article>
+",
+        anchor(&letter.recipient),
+        escape(&letter.recipient),
+        escape(&letter.content),
+        signature(letter)
+    );
+
+    page(
+        &format!("To {} - {TITLE}", letter.recipient),
+        "../style.css",
+        &body,
+    )
+}
+
+/// Writes a static site with an index of all letters by recipient and a page
+/// per letter to `dir`. Anonymous letters never contain their sender.
+pub fn write_archive(letters: &[Letter], dir: &Path) -> std::io::Result<()> {
+    let letters_dir = dir.join("letters");
+    fs::create_dir_all(&letters_dir)?;
+
+    fs::write(dir.join("style.css"), STYLE)?;
+    fs::write(dir.join("index.html"), index_page(letters))?;
+    for letter in letters {
+        fs::write(
+            letters_dir.join(format!("{}.html", letter.id)),
+            letter_page(letter),
+        )?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,158 @@
+use diesel::prelude::*;
+
+use crate::filter::neutralize_mentions;
+use crate::model::{AuditEvent, NewAuditEvent};
+
+/// Something a moderator did, as stored in the audit log.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Delete,
+    AllowLetters,
+    AddRecipient,
+    Publish,
+    Feature,
+    ReadingOrder,
+    Vote,
+    Import,
+    Block,
+    Unblock,
+}
+
+impl Action {
+    pub const ALL: [Action; 10] = [
+        Action::Delete,
+        Action::AllowLetters,
+        Action::AddRecipient,
+        Action::Publish,
+        Action::Feature,
+        Action::ReadingOrder,
+        Action::Vote,
+        Action::Import,
+        Action::Block,
+        Action::Unblock,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Action::Delete => "delete",
+            Action::AllowLetters => "allow_letters",
+            Action::AddRecipient => "add_recipient",
+            Action::Publish => "publish",
+            Action::Feature => "feature",
+            Action::ReadingOrder => "reading_order",
+            Action::Vote => "vote",
+            Action::Import => "import",
+            Action::Block => "block",
+            Action::Unblock => "unblock",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Action::ALL
+            .into_iter()
+            .find(|action| action.as_str() == name)
+    }
+
+    /// An event for this action, taken by the user with ID `actor_id`.
+    pub fn by(self, actor_id: impl ToString) -> NewAuditEvent {
+        NewAuditEvent {
+            action: self.as_str().to_owned(),
+            actor_id: actor_id.to_string(),
+            target: None,
+            details: None,
+        }
+    }
+}
+
+impl NewAuditEvent {
+    /// What the action was taken on, like [`letter`] or [`user`].
+    pub fn on(mut self, target: String) -> Self {
+        self.target = Some(target);
+        self
+    }
+
+    pub fn details(mut self, details: impl ToString) -> Self {
+        self.details = Some(details.to_string());
+        self
+    }
+}
+
+/// The target of an action on a letter.
+pub fn letter(id: i32) -> String {
+    format!("letter #{id}")
+}
+
+/// The target of an action on a recipient.
+pub fn recipient(name: &str) -> String {
+    format!("recipient {name}")
+}
+
+/// The target of an action in a channel.
+pub fn channel(id: impl std::fmt::Display) -> String {
+    format!("channel {id}")
+}
+
+/// The target of an action on a user, by ID.
+pub fn user(id: impl std::fmt::Display) -> String {
+    format!("user {id}")
+}
+
+pub fn record(conn: &mut SqliteConnection, event: &NewAuditEvent) -> QueryResult<()> {
+    use crate::schema::audit_events::dsl::audit_events;
+
+    diesel::insert_into(audit_events)
+        .values(event)
+        .execute(conn)
+        .map(|_| ())
+}
+
+/// Which events to look up. Unset fields match every event.
+#[derive(Default)]
+pub struct EventFilter {
+    pub action: Option<Action>,
+    pub actor_id: Option<String>,
+    pub target: Option<String>,
+}
+
+/// The latest `limit` events matching `filter`, newest first.
+pub fn events(
+    conn: &mut SqliteConnection,
+    filter: &EventFilter,
+    limit: i64,
+) -> QueryResult<Vec<AuditEvent>> {
+    use crate::schema::audit_events::dsl::{action, actor_id, audit_events, id, target};
+
+    let mut query = audit_events.into_boxed();
+    if let Some(wanted) = filter.action {
+        query = query.filter(action.eq(wanted.as_str()));
+    }
+    if let Some(wanted) = &filter.actor_id {
+        query = query.filter(actor_id.eq(wanted));
+    }
+    if let Some(wanted) = &filter.target {
+        query = query.filter(target.eq(wanted));
+    }
+
+    query.order(id.desc()).limit(limit).load(conn)
+}
+
+impl std::fmt::Display for AuditEvent {
+    /// One line of `/auditlog`. Targets and details can be typed by anyone,
+    /// like recipient names, so their mentions are kept from pinging.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "`{}` **{}** by <@{}>",
+            self.created_at.format("%Y-%m-%d %H:%M:%S"),
+            self.action,
+            self.actor_id
+        )?;
+        if let Some(target) = &self.target {
+            write!(f, " on {}", neutralize_mentions(target))?;
+        }
+        if let Some(details) = &self.details {
+            write!(f, ": {}", neutralize_mentions(details))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,71 @@
+use diesel::prelude::*;
+
+use crate::encryption::TryEncrypted;
+use crate::model::{BlockedUser, NO_SENDER_ID};
+
+/// Stops a user from sending letters, or changes why they were blocked.
+pub fn block(conn: &mut SqliteConnection, blocked: &BlockedUser) -> QueryResult<()> {
+    use crate::schema::blocked_users::dsl::{blocked_by, blocked_users, reason, user_id};
+
+    diesel::insert_into(blocked_users)
+        .values(blocked)
+        .on_conflict(user_id)
+        .do_update()
+        .set((
+            reason.eq(&blocked.reason),
+            blocked_by.eq(&blocked.blocked_by),
+        ))
+        .execute(conn)
+        .map(|_| ())
+}
+
+/// Blocks whoever sent the letter logged in the given audit message, and
+/// returns their ID and name, or nothing if the letter is gone or was imported
+/// without a sender ID.
+pub fn block_sender(
+    conn: &mut SqliteConnection,
+    audit_message: &str,
+    why: &str,
+    moderator: &str,
+) -> QueryResult<Option<(String, String)>> {
+    use crate::schema::letters::dsl::{letters, message_id, sender, sender_id};
+
+    conn.transaction(|conn| {
+        let Some((TryEncrypted(name), TryEncrypted(id))) = letters
+            .filter(message_id.eq(audit_message))
+            .select((sender, sender_id))
+            .first::<(TryEncrypted, TryEncrypted)>(conn)
+            .optional()?
+        else {
+            return Ok(None);
+        };
+        if id == NO_SENDER_ID {
+            return Ok(None);
+        }
+        block(
+            conn,
+            &BlockedUser {
+                user_id: id.clone(),
+                reason: why.to_owned(),
+                blocked_by: moderator.to_owned(),
+            },
+        )?;
+        Ok(Some((id, name)))
+    })
+}
+
+/// Lets a user send letters again. Returns whether they were blocked.
+pub fn unblock(conn: &mut SqliteConnection, blocked: &str) -> QueryResult<bool> {
+    use crate::schema::blocked_users::dsl::{blocked_users, user_id};
+
+    diesel::delete(blocked_users.filter(user_id.eq(blocked)))
+        .execute(conn)
+        .map(|deleted| deleted > 0)
+}
+
+/// Whether a user may not send letters.
+pub fn is_blocked(conn: &mut SqliteConnection, user: &str) -> QueryResult<bool> {
+    use crate::schema::blocked_users::dsl::{blocked_users, user_id};
+
+    diesel::select(diesel::dsl::exists(blocked_users.filter(user_id.eq(user)))).get_result(conn)
+}
@@ -0,0 +1,147 @@
+use tiny_skia::{
+    FillRule, GradientStop, LinearGradient, Paint, Pixmap, Point, SpreadMode, Transform,
+};
+
+use crate::draw::{self, rgb};
+use crate::model::Letter;
+
+const WIDTH: u32 = 1080;
+const MARGIN: f32 = 48.0;
+const PADDING: f32 = 64.0;
+
+const TITLE_SIZE: f32 = 44.0;
+const BODY_SIZE: f32 = 28.0;
+const LINE_HEIGHT: f32 = BODY_SIZE * 1.5;
+const SIGNATURE_SIZE: f32 = 30.0;
+const FOOTER_SIZE: f32 = 20.0;
+
+/// Renders a card on a thread meant for blocking work, as long letters take
+/// a while to draw.
+pub async fn render_card_in_background(letter: &Letter) -> Result<Vec<u8>, String> {
+    let letter = letter.clone();
+    tokio::task::spawn_blocking(move || render_card(&letter))
+        .await
+        .map_err(|e| format!("Could not render card: {e}"))?
+}
+
+/// Renders a letter as a PNG valentine card. Anonymous letters are signed
+/// "Anonymous" instead of with their sender.
+pub fn render_card(letter: &Letter) -> Result<Vec<u8>, String> {
+    let regular = draw::regular();
+    let bold = draw::bold();
+
+    let text_x = MARGIN + PADDING;
+    let text_width = WIDTH as f32 - 2.0 * text_x;
+    let lines = draw::wrap(&regular, BODY_SIZE, &letter.content, text_width);
+
+    let title_y = MARGIN + PADDING + TITLE_SIZE;
+    let body_y = title_y + TITLE_SIZE;
+    let signature_y = body_y + lines.len() as f32 * LINE_HEIGHT + SIGNATURE_SIZE;
+    let footer_y = signature_y + PADDING + FOOTER_SIZE;
+    let height = (footer_y + PADDING / 2.0 + MARGIN).ceil() as u32;
+
+    let mut pixmap = Pixmap::new(WIDTH, height).ok_or("Letter is too long to render as a card")?;
+
+    let background = Paint {
+        shader: LinearGradient::new(
+            Point::from_xy(0.0, 0.0),
+            Point::from_xy(WIDTH as f32, height as f32),
+            vec![
+                GradientStop::new(0.0, rgb(0xffd1dc)),
+                GradientStop::new(1.0, rgb(0xff8fb1)),
+            ],
+            SpreadMode::Pad,
+            Transform::identity(),
+        )
+        .ok_or("Could not create background")?,
+        ..Default::default()
+    };
+    pixmap.fill_rect(
+        tiny_skia::Rect::from_xywh(0.0, 0.0, WIDTH as f32, height as f32)
+            .ok_or("Could not create background")?,
+        &background,
+        Transform::identity(),
+        None,
+    );
+
+    let paper = draw::rounded_rect(
+        MARGIN,
+        MARGIN,
+        WIDTH as f32 - 2.0 * MARGIN,
+        height as f32 - 2.0 * MARGIN,
+        24.0,
+    )
+    .ok_or("Could not create card")?;
+    pixmap.fill_path(
+        &paper,
+        &draw::paint(rgb(0xfffafc)),
+        FillRule::Winding,
+        Transform::identity(),
+        None,
+    );
+
+    if let Some(heart) = draw::heart(WIDTH as f32 - text_x - 20.0, title_y - 16.0, 48.0) {
+        pixmap.fill_path(
+            &heart,
+            &draw::paint(rgb(0xff69b4)),
+            FillRule::Winding,
+            Transform::identity(),
+            None,
+        );
+    }
+
+    draw::draw_text(
+        &mut pixmap,
+        &bold,
+        TITLE_SIZE,
+        &format!("To {}", letter.recipient),
+        text_x,
+        title_y,
+        rgb(0xc71f5d),
+    );
+
+    for (line, y) in lines
+        .iter()
+        .zip((1..).map(|i| body_y + i as f32 * LINE_HEIGHT))
+    {
+        draw::draw_text(
+            &mut pixmap,
+            &regular,
+            BODY_SIZE,
+            line,
+            text_x,
+            y,
+            rgb(0x3b1f2b),
+        );
+    }
+
+    let signature = if letter.anon {
+        "From Anonymous".to_owned()
+    } else {
+        format!("From {}", letter.sender)
+    };
+    draw::draw_text(
+        &mut pixmap,
+        &bold,
+        SIGNATURE_SIZE,
+        &signature,
+        WIDTH as f32 - text_x - draw::text_width(&bold, SIGNATURE_SIZE, &signature),
+        signature_y + SIGNATURE_SIZE,
+        rgb(0xc71f5d),
+    );
+
+    let footer = "2023 Classroom of the Elite Valentine's Event";
+    draw::draw_text(
+        &mut pixmap,
+        &regular,
+        FOOTER_SIZE,
+        footer,
+        (WIDTH as f32 - draw::text_width(&regular, FOOTER_SIZE, footer)) / 2.0,
+        footer_y,
+        rgb(0x9e5a76),
+    );
+
+    pixmap
+        .encode_png()
+        .map_err(|e| format!("Could not encode card: {e}"))
+}
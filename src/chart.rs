@@ -0,0 +1,227 @@
+use chrono::{Duration, NaiveDate};
+use tiny_skia::{FillRule, Pixmap, Rect, Transform};
+
+use crate::draw::{self, rgb};
+
+const WIDTH: u32 = 1000;
+const MARGIN: f32 = 40.0;
+const TITLE_SIZE: f32 = 32.0;
+const LABEL_SIZE: f32 = 20.0;
+const PLOT_TOP: f32 = MARGIN + TITLE_SIZE + 32.0;
+
+const ROW_HEIGHT: f32 = 40.0;
+const BAR_THICKNESS: f32 = 28.0;
+const MAX_LABEL_WIDTH: f32 = 320.0;
+/// Bar charts with more rows than this add up the smallest ones in one bar.
+const MAX_BARS: usize = 25;
+const TIMELINE_HEIGHT: u32 = 500;
+
+fn canvas(height: u32, title: &str) -> Result<Pixmap, String> {
+    let mut pixmap = Pixmap::new(WIDTH, height).ok_or("Chart is too large to render")?;
+    pixmap.fill(rgb(0xfffafc));
+    draw::draw_text(
+        &mut pixmap,
+        &draw::bold(),
+        TITLE_SIZE,
+        title,
+        MARGIN,
+        MARGIN + TITLE_SIZE,
+        rgb(0xc71f5d),
+    );
+    Ok(pixmap)
+}
+
+fn bar(pixmap: &mut Pixmap, x: f32, y: f32, width: f32, height: f32) {
+    if let Some(path) = draw::rounded_rect(x, y, width.max(1.0), height.max(1.0), 4.0) {
+        pixmap.fill_path(
+            &path,
+            &draw::paint(rgb(0xff69b4)),
+            FillRule::Winding,
+            Transform::identity(),
+            None,
+        );
+    }
+}
+
+fn line(pixmap: &mut Pixmap, x: f32, y: f32, width: f32) {
+    if let Some(rect) = Rect::from_xywh(x, y, width, 1.0) {
+        pixmap.fill_rect(
+            rect,
+            &draw::paint(rgb(0xe8c5d3)),
+            Transform::identity(),
+            None,
+        );
+    }
+}
+
+fn encode(pixmap: Pixmap) -> Result<Vec<u8>, String> {
+    pixmap
+        .encode_png()
+        .map_err(|e| format!("Could not encode chart: {e}"))
+}
+
+/// A horizontal bar per label, in the order given, which should be largest
+/// first. Past [`MAX_BARS`], the last ones share a bar.
+pub fn bar_chart(title: &str, values: &[(String, usize)]) -> Result<Vec<u8>, String> {
+    let mut values = values.to_vec();
+    if values.len() > MAX_BARS {
+        let rest = values.split_off(MAX_BARS - 1);
+        let others = rest.iter().map(|(_, value)| value).sum();
+        values.push((format!("{} others", rest.len()), others));
+    }
+
+    let regular = draw::regular();
+    let height = (PLOT_TOP + values.len().max(1) as f32 * ROW_HEIGHT + MARGIN) as u32;
+    let mut pixmap = canvas(height, title)?;
+
+    let max = values.iter().map(|(_, value)| *value).max().unwrap_or(0);
+    if max == 0 {
+        draw::draw_text(
+            &mut pixmap,
+            &regular,
+            LABEL_SIZE,
+            "No letters yet",
+            MARGIN,
+            PLOT_TOP + LABEL_SIZE,
+            rgb(0x9e5a76),
+        );
+        return encode(pixmap);
+    }
+
+    let label_width = values
+        .iter()
+        .map(|(label, _)| draw::text_width(&regular, LABEL_SIZE, label))
+        .fold(0.0, f32::max)
+        .min(MAX_LABEL_WIDTH);
+    let value_width = draw::text_width(&regular, LABEL_SIZE, &max.to_string());
+    let bars_x = MARGIN + label_width + 16.0;
+    let bars_width = WIDTH as f32 - bars_x - value_width - 8.0 - MARGIN;
+
+    for (i, (label, value)) in values.iter().enumerate() {
+        let row_y = PLOT_TOP + i as f32 * ROW_HEIGHT;
+        let baseline = row_y + (ROW_HEIGHT + LABEL_SIZE) / 2.0 - 4.0;
+        let label = draw::wrap(&regular, LABEL_SIZE, label, label_width)
+            .into_iter()
+            .next()
+            .unwrap_or_default();
+        draw::draw_text(
+            &mut pixmap,
+            &regular,
+            LABEL_SIZE,
+            &label,
+            MARGIN,
+            baseline,
+            rgb(0x3b1f2b),
+        );
+
+        let width = bars_width * *value as f32 / max as f32;
+        bar(
+            &mut pixmap,
+            bars_x,
+            row_y + (ROW_HEIGHT - BAR_THICKNESS) / 2.0,
+            width,
+            BAR_THICKNESS,
+        );
+        draw::draw_text(
+            &mut pixmap,
+            &regular,
+            LABEL_SIZE,
+            &value.to_string(),
+            bars_x + width + 8.0,
+            baseline,
+            rgb(0x3b1f2b),
+        );
+    }
+
+    encode(pixmap)
+}
+
+/// A vertical bar per day between the first and last day, including days
+/// without any submissions.
+pub fn timeline(title: &str, values: &[(NaiveDate, usize)]) -> Result<Vec<u8>, String> {
+    let regular = draw::regular();
+    let mut pixmap = canvas(TIMELINE_HEIGHT, title)?;
+
+    let (Some((first, _)), Some((last, _))) = (values.first(), values.last()) else {
+        draw::draw_text(
+            &mut pixmap,
+            &regular,
+            LABEL_SIZE,
+            "No letters yet",
+            MARGIN,
+            PLOT_TOP + LABEL_SIZE,
+            rgb(0x9e5a76),
+        );
+        return encode(pixmap);
+    };
+
+    let days: Vec<(NaiveDate, usize)> = (0..=(*last - *first).num_days())
+        .map(|offset| {
+            let day = *first + Duration::days(offset);
+            let count = values
+                .iter()
+                .find(|(d, _)| *d == day)
+                .map_or(0, |(_, count)| *count);
+            (day, count)
+        })
+        .collect();
+    let max = days
+        .iter()
+        .map(|(_, count)| *count)
+        .max()
+        .unwrap_or(0)
+        .max(1);
+
+    let axis_width = draw::text_width(&regular, LABEL_SIZE, &max.to_string()) + 12.0;
+    let plot_x = MARGIN + axis_width;
+    let plot_width = WIDTH as f32 - plot_x - MARGIN;
+    let plot_bottom = TIMELINE_HEIGHT as f32 - MARGIN - LABEL_SIZE - 8.0;
+    let plot_height = plot_bottom - PLOT_TOP;
+
+    for (value, y) in [(max, PLOT_TOP), (0, plot_bottom)] {
+        line(&mut pixmap, plot_x, y, plot_width);
+        draw::draw_text(
+            &mut pixmap,
+            &regular,
+            LABEL_SIZE,
+            &value.to_string(),
+            MARGIN,
+            y + LABEL_SIZE / 2.0 - 2.0,
+            rgb(0x9e5a76),
+        );
+    }
+
+    let slot = plot_width / days.len() as f32;
+    let label_width = draw::text_width(&regular, LABEL_SIZE, "00-00") + 12.0;
+    let label_every = (label_width / slot).ceil().max(1.0) as usize;
+
+    for (i, (day, count)) in days.iter().enumerate() {
+        let x = plot_x + i as f32 * slot;
+        let height = plot_height * *count as f32 / max as f32;
+        if *count > 0 {
+            bar(
+                &mut pixmap,
+                x + slot * 0.15,
+                plot_bottom - height,
+                slot * 0.7,
+                height,
+            );
+        }
+
+        if i % label_every == 0 {
+            let label = day.format("%m-%d").to_string();
+            let width = draw::text_width(&regular, LABEL_SIZE, &label);
+            draw::draw_text(
+                &mut pixmap,
+                &regular,
+                LABEL_SIZE,
+                &label,
+                x + (slot - width) / 2.0,
+                plot_bottom + LABEL_SIZE + 8.0,
+                rgb(0x3b1f2b),
+            );
+        }
+    }
+
+    encode(pixmap)
+}
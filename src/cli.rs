@@ -0,0 +1,113 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
+use diesel::SqliteConnection;
+
+use crate::archive;
+use crate::encryption;
+use crate::export::{self, ExportFormat};
+use crate::import::{self, ImportFormat};
+use crate::podcast;
+
+/// Discord bot for the COTE Valentine's event. Without a subcommand the bot is started.
+#[derive(Parser)]
+#[command(version, about)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<CliCommand>,
+}
+
+#[derive(Subcommand)]
+pub enum CliCommand {
+    /// Export all letters to a file, or to stdout if no output is given
+    Export {
+        #[arg(short, long, value_enum, default_value_t = ExportFormat::Json)]
+        format: ExportFormat,
+        /// Remove the sender from anonymous letters
+        #[arg(short, long)]
+        redact: bool,
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Write a static HTML archive of all letters to a directory
+    Archive { dir: PathBuf },
+    /// Import letters from a JSON or CSV file in the same format as the export
+    Import {
+        file: PathBuf,
+        /// Defaults to the file extension
+        #[arg(short, long, value_enum)]
+        format: Option<ImportFormat>,
+        /// Only check the file without importing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Write a reading script of the letters featured on the podcast
+    PodcastScript {
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Encrypt every letter with ENCRYPTION_KEY, including those stored in plaintext or with
+    /// PREVIOUS_ENCRYPTION_KEY. Without ENCRYPTION_KEY, decrypt them
+    RotateKey,
+}
+
+pub fn run(command: CliCommand, conn: &mut SqliteConnection) -> Result<(), String> {
+    match command {
+        CliCommand::Export {
+            format,
+            redact,
+            output,
+        } => {
+            let letters = export::load_letters(conn)
+                .map_err(|e| format!("Error while reading letters: {e}"))?;
+            let data = export::export(&letters, format, redact)?;
+            write_output(output, &data)?;
+            eprintln!("Exported {} letters", letters.len());
+        }
+        CliCommand::Archive { dir } => {
+            let letters = export::load_letters(conn)
+                .map_err(|e| format!("Error while reading letters: {e}"))?;
+            archive::write_archive(&letters, &dir)
+                .map_err(|e| format!("Could not write to {}: {e}", dir.display()))?;
+            eprintln!("Archived {} letters in {}", letters.len(), dir.display());
+        }
+        CliCommand::Import {
+            file,
+            format,
+            dry_run,
+        } => {
+            let format = format
+                .or_else(|| ImportFormat::from_filename(&file.to_string_lossy()))
+                .ok_or("Unknown file format, specify one with --format")?;
+            let data =
+                fs::read(&file).map_err(|e| format!("Could not read {}: {e}", file.display()))?;
+            let report = import::import(conn, &data, format, dry_run).map_err(|e| e.to_string())?;
+            eprintln!("{}", report.summary(usize::MAX));
+        }
+        CliCommand::PodcastScript { output } => {
+            let featured = podcast::load_featured(conn)
+                .map_err(|e| format!("Error while reading letters: {e}"))?;
+            write_output(output, podcast::script(&featured).as_bytes())?;
+            eprintln!("Wrote a script for {} letters", featured.len());
+        }
+        CliCommand::RotateKey => {
+            let rotated = encryption::rotate(conn)
+                .map_err(|e| format!("Error while rotating the key: {e}"))?;
+            eprintln!("Rewrote {rotated} letters");
+        }
+    }
+
+    Ok(())
+}
+
+fn write_output(output: Option<PathBuf>, data: &[u8]) -> Result<(), String> {
+    match output {
+        Some(path) => fs::write(&path, data)
+            .map_err(|e| format!("Could not write to {}: {e}", path.display())),
+        None => io::stdout()
+            .write_all(data)
+            .map_err(|e| format!("Could not write to stdout: {e}")),
+    }
+}
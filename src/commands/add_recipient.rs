@@ -1,68 +1,84 @@
-use diesel::insert_into;
-use diesel::prelude::*;
-use diesel::SqliteConnection;
-
 use serenity::{
+    async_trait,
     builder::CreateApplicationCommand,
-    model::{
-        prelude::{
-            command::CommandOptionType,
-            interaction::application_command::ApplicationCommandInteraction,
-        },
-        Permissions,
+    model::prelude::{
+        command::CommandOptionType, interaction::application_command::ApplicationCommandInteraction,
     },
     prelude::Context,
 };
 
-use super::{as_boolean, as_string};
+use super::{as_boolean, as_string, CommandError, SlashCommand};
 
+use crate::audit::{self, Action};
 use crate::model::Recipient;
-use crate::schema::recipients::dsl::recipients;
+use crate::permissions::ModAction;
+use crate::repository::{LetterRepository, Repository};
 
-pub async fn run(
-    command: &ApplicationCommandInteraction,
-    _ctx: &Context,
-    db_conn: &mut SqliteConnection,
-) -> Result<Option<String>, String> {
-    let new: Recipient = command.try_into()?;
-    insert_into(recipients)
-        .values(&new)
-        .execute(db_conn)
-        .map_err(|e| format!("Something went wrong while adding person: \n{e}"))?;
+pub const NAME: &str = "add_recipient";
 
-    Ok(Some(format!(
-        "Done adding {} person {}",
-        {
-            if new.is_real {
-                "real"
-            } else {
-                "fictional"
-            }
-        },
-        new.fullname
-    )))
-}
+pub struct AddRecipientCommand;
 
-pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
-    command
-        .name("add_recipient")
-        .description("adds a recipient to the autocomplete list")
-        .create_option(|option| {
-            option
-                .name("name")
-                .description("name of the person to add")
-                .kind(CommandOptionType::String)
-                .required(true)
-        })
-        .create_option(|option| {
-            option
-                .name("is_real")
-                .description("whether this person is a real human")
-                .kind(CommandOptionType::Boolean)
-                .required(true)
-        })
-        .dm_permission(false)
-        .default_member_permissions(Permissions::MANAGE_GUILD)
+#[async_trait]
+impl SlashCommand for AddRecipientCommand {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn action(&self) -> Option<ModAction> {
+        Some(ModAction::Recipients)
+    }
+
+    fn register<'a>(
+        &self,
+        command: &'a mut CreateApplicationCommand,
+    ) -> &'a mut CreateApplicationCommand {
+        command
+            .name(NAME)
+            .description("adds a recipient to the autocomplete list")
+            .create_option(|option| {
+                option
+                    .name("name")
+                    .description("name of the person to add")
+                    .kind(CommandOptionType::String)
+                    .required(true)
+            })
+            .create_option(|option| {
+                option
+                    .name("is_real")
+                    .description("whether this person is a real human")
+                    .kind(CommandOptionType::Boolean)
+                    .required(true)
+            })
+            .dm_permission(false)
+    }
+
+    async fn run(
+        &self,
+        command: &ApplicationCommandInteraction,
+        _ctx: &Context,
+        db: &Repository,
+    ) -> Result<Option<String>, CommandError> {
+        let new: Recipient = command.try_into()?;
+        let added = Action::AddRecipient
+            .by(command.user.id)
+            .on(audit::recipient(&new.fullname))
+            .details(if new.is_real { "real" } else { "fictional" });
+        let reply = format!(
+            "Done adding {} person {}",
+            {
+                if new.is_real {
+                    "real"
+                } else {
+                    "fictional"
+                }
+            },
+            new.fullname
+        );
+        db.add_recipient(new).await?;
+        db.record_event(added).await?;
+
+        Ok(Some(reply))
+    }
 }
 
 impl TryFrom<&ApplicationCommandInteraction> for Recipient {
@@ -73,7 +89,7 @@ impl TryFrom<&ApplicationCommandInteraction> for Recipient {
         Ok(Self {
             fullname: as_string(
                 options
-                    .get(0)
+                    .first()
                     .ok_or("No name".to_owned())?
                     .resolved
                     .as_ref()
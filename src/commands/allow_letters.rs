@@ -1,52 +1,74 @@
-use diesel::prelude::*;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
 use serenity::{
-    builder::CreateApplicationCommand,
-    model::{
-        prelude::interaction::application_command::ApplicationCommandInteraction, Permissions,
-    },
+    async_trait, builder::CreateApplicationCommand,
+    model::prelude::interaction::application_command::ApplicationCommandInteraction,
     prelude::Context,
 };
 
-use super::as_boolean;
-
-pub async fn run(
-    interaction: &ApplicationCommandInteraction,
-    _ctx: &Context,
-    _db_conn: &mut SqliteConnection,
-) -> Result<Option<(bool, String)>, String> {
-    as_boolean(
-        interaction
-            .data
-            .options
-            .get(0)
-            .ok_or("No option found")?
-            .resolved
-            .as_ref()
-            .ok_or("Expected boolean")?,
-    )
-    .map(|val| {
-        Some((
-            *val,
-            format!(
-                "Set letters to {}",
-                if *val { "allowed" } else { "not allowed" }
-            ),
-        ))
-    })
-    .map_err(|_| "Something went wrong".to_string())
+use super::{as_boolean, CommandError, SlashCommand};
+use crate::audit::Action;
+use crate::permissions::ModAction;
+use crate::repository::{LetterRepository, Repository};
+
+pub const NAME: &str = "allow_letters";
+
+pub struct AllowLettersCommand {
+    /// Shared with `/sendletter`.
+    pub letters_allowed: Arc<AtomicBool>,
 }
 
-pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
-    command
-        .name("allow_letters")
-        .description("sets whether letters are allowed")
-        .create_option(|option| {
-            option
-                .kind(serenity::model::prelude::command::CommandOptionType::Boolean)
-                .name("allowed")
-                .description("whether to allow letters")
-                .required(true)
-        })
-        .dm_permission(false)
-        .default_member_permissions(Permissions::MANAGE_GUILD)
+#[async_trait]
+impl SlashCommand for AllowLettersCommand {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn action(&self) -> Option<ModAction> {
+        Some(ModAction::AllowLetters)
+    }
+
+    fn register<'a>(
+        &self,
+        command: &'a mut CreateApplicationCommand,
+    ) -> &'a mut CreateApplicationCommand {
+        command
+            .name(NAME)
+            .description("sets whether letters are allowed")
+            .create_option(|option| {
+                option
+                    .kind(serenity::model::prelude::command::CommandOptionType::Boolean)
+                    .name("allowed")
+                    .description("whether to allow letters")
+                    .required(true)
+            })
+            .dm_permission(false)
+    }
+
+    async fn run(
+        &self,
+        interaction: &ApplicationCommandInteraction,
+        _ctx: &Context,
+        db: &Repository,
+    ) -> Result<Option<String>, CommandError> {
+        let allowed = *as_boolean(
+            interaction
+                .data
+                .options
+                .first()
+                .ok_or("No option found")?
+                .resolved
+                .as_ref()
+                .ok_or("Expected boolean")?,
+        )
+        .map_err(|_| CommandError::Malformed("allowed is not a boolean"))?;
+
+        self.letters_allowed.store(allowed, Ordering::SeqCst);
+        let state = if allowed { "allowed" } else { "not allowed" };
+        db.record_event(Action::AllowLetters.by(interaction.user.id).details(state))
+            .await?;
+
+        Ok(Some(format!("Set letters to {state}")))
+    }
 }
@@ -0,0 +1,143 @@
+use serenity::{
+    async_trait,
+    builder::CreateApplicationCommand,
+    model::prelude::{
+        command::CommandOptionType,
+        interaction::application_command::{ApplicationCommandInteraction, CommandDataOptionValue},
+    },
+    prelude::Context,
+};
+
+use super::{as_string, as_user, find_option, CommandError, SlashCommand};
+use crate::audit::{self, events, Action, EventFilter};
+use crate::permissions::ModAction;
+use crate::repository::Repository;
+
+const DEFAULT_LENGTH: i64 = 10;
+
+/// Discord does not send messages longer than this.
+const MAX_MESSAGE_LENGTH: usize = 2000;
+
+pub const NAME: &str = "auditlog";
+
+pub struct AuditLogCommand;
+
+#[async_trait]
+impl SlashCommand for AuditLogCommand {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn action(&self) -> Option<ModAction> {
+        Some(ModAction::AuditLog)
+    }
+
+    fn register<'a>(
+        &self,
+        command: &'a mut CreateApplicationCommand,
+    ) -> &'a mut CreateApplicationCommand {
+        command
+            .name(NAME)
+            .description("shows what moderators did, newest first")
+            .create_option(|option| {
+                option
+                    .name("action")
+                    .description("only show this kind of action")
+                    .kind(CommandOptionType::String)
+                    .required(false);
+                for action in Action::ALL {
+                    option.add_string_choice(action.as_str(), action.as_str());
+                }
+                option
+            })
+            .create_option(|option| {
+                option
+                    .name("moderator")
+                    .description("only show what this moderator did")
+                    .kind(CommandOptionType::User)
+                    .required(false)
+            })
+            .create_option(|option| {
+                option
+                    .name("letter")
+                    .description("only show actions on the letter with this ID")
+                    .kind(CommandOptionType::Integer)
+                    .min_int_value(1)
+                    .required(false)
+            })
+            .create_option(|option| {
+                option
+                    .name("user")
+                    .description("only show actions on this user, like blocking them")
+                    .kind(CommandOptionType::User)
+                    .required(false)
+            })
+            .create_option(|option| {
+                option
+                    .name("length")
+                    .description("how many actions to show")
+                    .kind(CommandOptionType::Integer)
+                    .min_int_value(1)
+                    .max_int_value(25)
+                    .required(false)
+            })
+            .dm_permission(false)
+    }
+
+    async fn run(
+        &self,
+        command: &ApplicationCommandInteraction,
+        _ctx: &Context,
+        db: &Repository,
+    ) -> Result<Option<String>, CommandError> {
+        let options = &command.data.options;
+        let as_integer = |name| match find_option(options, name) {
+            Some(CommandDataOptionValue::Integer(val)) => Some(*val),
+            _ => None,
+        };
+        let user_id = |name| {
+            find_option(options, name)
+                .and_then(|user| as_user(user).ok())
+                .map(|user| user.id)
+        };
+
+        let action = match find_option(options, "action").and_then(|val| as_string(val).ok()) {
+            Some(name) => {
+                Some(Action::from_name(name).ok_or(CommandError::Malformed("unknown action"))?)
+            }
+            None => None,
+        };
+        let target = match (as_integer("letter"), user_id("user")) {
+            (Some(_), Some(_)) => return Err("Filter by either a letter or a user".into()),
+            (Some(letter), None) => Some(audit::letter(
+                i32::try_from(letter).map_err(|_| "Letter ID is out of range")?,
+            )),
+            (None, Some(user)) => Some(audit::user(user)),
+            (None, None) => None,
+        };
+        let filter = EventFilter {
+            action,
+            actor_id: user_id("moderator").map(|id| id.to_string()),
+            target,
+        };
+        let length = as_integer("length").unwrap_or(DEFAULT_LENGTH);
+
+        let found = db.query(move |conn| events(conn, &filter, length)).await?;
+        if found.is_empty() {
+            return Ok(Some("No matching actions were logged.".to_owned()));
+        }
+
+        let mut log = String::new();
+        for event in found {
+            let line = event.to_string();
+            if log.len() + line.len() + 1 > MAX_MESSAGE_LENGTH {
+                break;
+            }
+            if !log.is_empty() {
+                log.push('\n');
+            }
+            log.push_str(&line);
+        }
+        Ok(Some(log))
+    }
+}
@@ -0,0 +1,198 @@
+use serenity::{
+    async_trait,
+    builder::{CreateApplicationCommand, CreateInteractionResponse},
+    model::prelude::{
+        command::CommandOptionType,
+        component::{ActionRowComponent, InputText, InputTextStyle},
+        interaction::{
+            application_command::ApplicationCommandInteraction,
+            message_component::MessageComponentInteraction, modal::ModalSubmitInteraction,
+            InteractionResponseType,
+        },
+    },
+    prelude::Context,
+};
+use tracing::info;
+
+use super::{as_string, as_user, find_option, CommandError, SlashCommand};
+use crate::audit::{self, Action};
+use crate::blocklist;
+use crate::discord::{build, Discord};
+use crate::model::BlockedUser;
+use crate::permissions::ModAction;
+use crate::repository::{LetterRepository, Repository};
+
+pub const NAME: &str = "block";
+
+/// Longest reason a moderator can give when blocking from the audit channel.
+const REASON_MAX_LENGTH: u16 = 200;
+
+pub struct BlockCommand;
+
+#[async_trait]
+impl SlashCommand for BlockCommand {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn action(&self) -> Option<ModAction> {
+        Some(ModAction::Block)
+    }
+
+    fn register<'a>(
+        &self,
+        command: &'a mut CreateApplicationCommand,
+    ) -> &'a mut CreateApplicationCommand {
+        command
+            .name(NAME)
+            .description("Stops someone from sending letters")
+            .create_option(|option| {
+                option
+                    .name("user")
+                    .description("Who to block")
+                    .kind(CommandOptionType::User)
+                    .required(true)
+            })
+            .create_option(|option| {
+                option
+                    .name("reason")
+                    .description("Why they are blocked, for other moderators")
+                    .kind(CommandOptionType::String)
+                    .max_length(REASON_MAX_LENGTH)
+                    .required(true)
+            })
+            .dm_permission(false)
+    }
+
+    async fn run(
+        &self,
+        command: &ApplicationCommandInteraction,
+        _ctx: &Context,
+        db: &Repository,
+    ) -> Result<Option<String>, CommandError> {
+        let options = &command.data.options;
+        let user = find_option(options, "user")
+            .and_then(|user| as_user(user).ok())
+            .ok_or(CommandError::Malformed("user is not a user"))?
+            .clone();
+        let reason = find_option(options, "reason")
+            .and_then(|reason| as_string(reason).ok())
+            .ok_or(CommandError::Malformed("reason is not a string"))?
+            .clone();
+
+        let event = Action::Block
+            .by(command.user.id)
+            .on(audit::user(user.id))
+            .details(&reason);
+        let blocked = BlockedUser {
+            user_id: user.id.to_string(),
+            reason,
+            blocked_by: command.user.id.to_string(),
+        };
+        db.query(move |conn| blocklist::block(conn, &blocked))
+            .await?;
+        db.record_event(event).await?;
+        info!("Blocked a user from sending letters");
+
+        Ok(Some(format!("{} can no longer send letters", user.tag())))
+    }
+
+    /// The block button on audit messages and the modal asking why.
+    fn custom_ids(&self) -> &'static [&'static str] {
+        &["block_sender", "block_modal"]
+    }
+
+    async fn component(
+        &self,
+        interaction: &MessageComponentInteraction,
+        ctx: &Context,
+        _db: &Repository,
+    ) -> Result<(), CommandError> {
+        handle_button(interaction, &ctx.http).await
+    }
+
+    async fn modal(
+        &self,
+        interaction: &mut ModalSubmitInteraction,
+        ctx: &Context,
+        db: &Repository,
+    ) -> Result<(), CommandError> {
+        handle_modal(interaction, &ctx.http, db).await
+    }
+}
+
+pub async fn handle_button(
+    interaction: &MessageComponentInteraction,
+    discord: &dyn Discord,
+) -> Result<(), CommandError> {
+    let modal = build(|response: &mut CreateInteractionResponse| {
+        response
+            .kind(InteractionResponseType::Modal)
+            .interaction_response_data(|data| {
+                data.custom_id("block_modal")
+                    .title("Block the sender of this letter")
+                    .components(|components| {
+                        components.create_action_row(|row| {
+                            row.create_input_text(|input| {
+                                input
+                                    .custom_id(interaction.message.id)
+                                    .label("Why are they blocked?")
+                                    .required(true)
+                                    .max_length(REASON_MAX_LENGTH.into())
+                                    .style(InputTextStyle::Paragraph)
+                            })
+                        })
+                    })
+            })
+    });
+    discord
+        .respond(interaction.id, &interaction.token, modal)
+        .await?;
+    Ok(())
+}
+
+pub async fn handle_modal(
+    interaction: &ModalSubmitInteraction,
+    discord: &dyn Discord,
+    db: &dyn LetterRepository,
+) -> Result<(), CommandError> {
+    let Some(ActionRowComponent::InputText(InputText {
+        custom_id: message_id,
+        value: reason,
+        ..
+    })) = interaction
+        .data
+        .components
+        .first()
+        .and_then(|row| row.components.first())
+    else {
+        return Err(CommandError::Malformed("block modal has no input"));
+    };
+
+    let (sender_id, sender) = db
+        .block_sender(
+            message_id.clone(),
+            reason.clone(),
+            interaction.user.id.to_string(),
+        )
+        .await?
+        .ok_or("This letter was deleted or has no sender ID, use /block instead")?;
+    let blocked = Action::Block
+        .by(interaction.user.id)
+        .on(audit::user(sender_id))
+        .details(reason);
+    db.record_event(blocked).await?;
+    info!("Blocked a user from sending letters");
+
+    let response = build(|response: &mut CreateInteractionResponse| {
+        response.interaction_response_data(|data| {
+            data.content(format!("{sender} can no longer send letters"))
+                .ephemeral(true)
+                .allowed_mentions(|mentions| mentions.empty_parse())
+        })
+    });
+    discord
+        .respond(interaction.id, &interaction.token, response)
+        .await?;
+    Ok(())
+}
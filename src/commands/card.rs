@@ -0,0 +1,99 @@
+use diesel::prelude::*;
+use serenity::{
+    async_trait,
+    builder::CreateApplicationCommand,
+    model::prelude::{
+        command::CommandOptionType,
+        interaction::{
+            application_command::{ApplicationCommandInteraction, CommandDataOptionValue},
+            InteractionResponseType,
+        },
+        AttachmentType,
+    },
+    prelude::Context,
+};
+
+use super::{find_option, CommandError, SlashCommand};
+use crate::card::render_card_in_background;
+use crate::logging;
+use crate::model::Letter;
+use crate::permissions::ModAction;
+use crate::repository::Repository;
+
+pub const NAME: &str = "card";
+
+pub struct CardCommand;
+
+#[async_trait]
+impl SlashCommand for CardCommand {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn action(&self) -> Option<ModAction> {
+        Some(ModAction::Review)
+    }
+
+    fn register<'a>(
+        &self,
+        command: &'a mut CreateApplicationCommand,
+    ) -> &'a mut CreateApplicationCommand {
+        command
+            .name(NAME)
+            .description("renders a letter as an image")
+            .create_option(|option| {
+                option
+                    .name("letter")
+                    .description("ID of the letter")
+                    .kind(CommandOptionType::Integer)
+                    .min_int_value(1)
+                    .required(true)
+            })
+            .dm_permission(false)
+    }
+
+    async fn run(
+        &self,
+        command: &ApplicationCommandInteraction,
+        ctx: &Context,
+        db: &Repository,
+    ) -> Result<Option<String>, CommandError> {
+        use crate::schema::letters::dsl::letters;
+
+        let Some(CommandDataOptionValue::Integer(letter_id)) =
+            find_option(&command.data.options, "letter")
+        else {
+            return Err("No letter ID found".into());
+        };
+        let letter_id = i32::try_from(*letter_id).map_err(|_| "Letter ID is out of range")?;
+        logging::record_letter(letter_id);
+
+        let letter: Letter = db
+            .query(move |conn| letters.find(letter_id).first(conn).optional())
+            .await?
+            .ok_or_else(|| format!("There is no letter #{letter_id}"))?;
+
+        // long letters take a while to draw
+        command
+            .create_interaction_response(ctx, |response| {
+                response
+                    .kind(InteractionResponseType::DeferredChannelMessageWithSource)
+                    .interaction_response_data(|message| message.ephemeral(true))
+            })
+            .await?;
+        let card = render_card_in_background(&letter).await?;
+
+        command
+            .create_followup_message(ctx, |message| {
+                message
+                    .add_file(AttachmentType::Bytes {
+                        data: card.into(),
+                        filename: format!("letter-{letter_id}.png"),
+                    })
+                    .ephemeral(true)
+            })
+            .await?;
+
+        Ok(None)
+    }
+}
@@ -1,5 +1,5 @@
-use diesel::prelude::*;
 use serenity::{
+    builder::{CreateInteractionResponse, EditMessage},
     model::prelude::{
         component::{ActionRowComponent, InputText, InputTextStyle},
         interaction::{
@@ -8,77 +8,91 @@ use serenity::{
         },
         MessageId,
     },
-    prelude::Context,
 };
+use tracing::info;
 
-use crate::{model::Letter, schema::letters::all_columns};
+use super::CommandError;
+use crate::audit::{self, Action};
+use crate::discord::{build, Discord};
+use crate::filter::neutralize_mentions;
+use crate::repository::LetterRepository;
+use crate::{logging, metrics};
 
-pub async fn handle_button(interaction: &MessageComponentInteraction, ctx: &Context) {
-    let Some(member) = &interaction.member else {
-        return;
-    };
-
-    if !member
-        .permissions
-        .expect("member should have permissions")
-        .manage_messages()
-    {
-        interaction
-            .create_interaction_response(ctx, |response| {
-                response.interaction_response_data(|data| {
-                    data.content(
-                        "You aren't allowed to do this. (Manage Messages permission required)",
-                    )
-                    .ephemeral(true)
-                })
-            })
-            .await
-            .unwrap();
-        return;
-    };
-
-    interaction
-        .create_interaction_response(ctx, |response| {
-            response
-                .kind(InteractionResponseType::Modal)
-                .interaction_response_data(|data| {
-                    data.custom_id("delete_modal")
-                        .title("You are about to delete a Valentines letter!")
-                        .components(|components| {
-                            components.create_action_row(|row| {
-                                row.create_input_text(|input| {
-                                    input
-                                        .custom_id(interaction.message.id)
-                                        .label("Just making sure!")
-                                        .required(false)
-                                        .placeholder("Don't make a mistake!")
-                                        .style(InputTextStyle::Short)
-                                })
+pub async fn handle_button(
+    interaction: &MessageComponentInteraction,
+    discord: &dyn Discord,
+) -> Result<(), CommandError> {
+    let modal = build(|response: &mut CreateInteractionResponse| {
+        response
+            .kind(InteractionResponseType::Modal)
+            .interaction_response_data(|data| {
+                data.custom_id("delete_modal")
+                    .title("You are about to delete a Valentines letter!")
+                    .components(|components| {
+                        components.create_action_row(|row| {
+                            row.create_input_text(|input| {
+                                input
+                                    .custom_id(interaction.message.id)
+                                    .label("Just making sure!")
+                                    .required(false)
+                                    .placeholder("Don't make a mistake!")
+                                    .style(InputTextStyle::Short)
                             })
                         })
-                })
-        })
-        .await
-        .unwrap();
+                    })
+            })
+    });
+    discord
+        .respond(interaction.id, &interaction.token, modal)
+        .await?;
+    Ok(())
 }
 
 pub async fn handle_modal(
-    interaction: &mut ModalSubmitInteraction,
-    ctx: &Context,
-    db_conn: &mut SqliteConnection,
-) {
-    let ActionRowComponent::InputText(InputText { custom_id: message_id, ..}) = interaction.data.components.get(0).unwrap().components.get(0).unwrap() else {panic!()};
-    let message_id = MessageId(message_id.parse().unwrap());
+    interaction: &ModalSubmitInteraction,
+    discord: &dyn Discord,
+    db: &dyn LetterRepository,
+) -> Result<(), CommandError> {
+    let Some(ActionRowComponent::InputText(InputText {
+        custom_id: message_id,
+        ..
+    })) = interaction
+        .data
+        .components
+        .first()
+        .and_then(|row| row.components.first())
+    else {
+        return Err(CommandError::Malformed("delete modal has no input"));
+    };
+    let message_id = MessageId(
+        message_id
+            .parse()
+            .map_err(|_| CommandError::Malformed("delete modal is not for a message"))?,
+    );
 
-    let deleted = delete_letter(message_id, db_conn).expect("Can delete letter");
-    interaction
+    let deleted = db
+        .delete_letter(message_id.to_string())
+        .await?
+        .ok_or("This letter was already deleted")?;
+    logging::record_letter(deleted.id);
+    db.record_event(
+        Action::Delete
+            .by(interaction.user.id)
+            .on(audit::letter(deleted.id)),
+    )
+    .await?;
+    metrics::LETTERS_DELETED.inc();
+    info!("Letter deleted");
+    let audit_message = interaction
         .message
-        .as_mut()
-        .unwrap()
-        .edit(ctx, |edit| {
-            use ellipse::Ellipse;
-            edit.components(|components| components).embed(|e| {
-                e.title(if deleted.anon {
+        .as_ref()
+        .ok_or(CommandError::Malformed("delete modal has no audit message"))?;
+    let edit = build(|edit: &mut EditMessage| {
+        use ellipse::Ellipse;
+        edit.components(|components| components)
+            .allowed_mentions(|mentions| mentions.empty_parse())
+            .embed(|e| {
+                e.title(neutralize_mentions(&if deleted.anon {
                     format!(
                         "Deleted: Sent anonymously by {} to {}",
                         deleted.sender, deleted.recipient
@@ -88,8 +102,10 @@ pub async fn handle_modal(
                         "Deleted: Sent by {} to {}",
                         deleted.sender, deleted.recipient
                     )
-                })
-                .description(&deleted.content.as_str().truncate_ellipse(50))
+                }))
+                .description(neutralize_mentions(
+                    &deleted.content.as_str().truncate_ellipse(50),
+                ))
                 .field(
                     "Deleted",
                     format!(
@@ -102,24 +118,20 @@ pub async fn handle_modal(
                 .footer(|f| f.text("2023 Classroom of the Elite Valentine's Event"))
                 .color((255, 0, 0))
             })
-        })
-        .await
-        .unwrap();
+    });
+    discord
+        .edit_message(audit_message.channel_id, audit_message.id, edit)
+        .await?;
 
-    interaction
-        .create_interaction_response(ctx, |response| {
-            response
-                .interaction_response_data(|data| data.content("Deleted a message").ephemeral(true))
+    let response = build(|response: &mut CreateInteractionResponse| {
+        response.interaction_response_data(|data| {
+            data.content("Deleted a message")
+                .ephemeral(true)
+                .allowed_mentions(|mentions| mentions.empty_parse())
         })
-        .await
-        .unwrap();
-}
-
-fn delete_letter(to_delete: MessageId, conn: &mut SqliteConnection) -> Result<Letter, String> {
-    use crate::schema::letters::dsl::{letters, message_id};
-
-    diesel::delete(letters.filter(message_id.eq(to_delete.to_string())))
-        .returning(all_columns)
-        .get_result(conn)
-        .map_err(|e| format!("Error {e}"))
+    });
+    discord
+        .respond(interaction.id, &interaction.token, response)
+        .await?;
+    Ok(())
 }
@@ -0,0 +1,144 @@
+use std::fmt;
+
+use diesel::r2d2::PoolError;
+use serenity::{
+    model::application::interaction::{Interaction, InteractionResponseType},
+    prelude::Context,
+};
+use tracing::{error, warn};
+
+use crate::import::ImportError;
+use crate::repository::RepositoryError;
+
+/// Everything that can go wrong while handling an interaction.
+#[derive(Debug)]
+pub enum CommandError {
+    /// Something the user can fix, shown to them as is.
+    User(String),
+    /// No database connection was available, or a query failed.
+    Database(String),
+    /// A request to Discord failed.
+    Discord(serenity::Error),
+    /// The interaction did not have the shape of the ones we register.
+    Malformed(&'static str),
+}
+
+impl CommandError {
+    /// What the user sees. Details of internal errors are only logged.
+    pub fn user_message(&self) -> String {
+        match self {
+            CommandError::User(message) => message.clone(),
+            CommandError::Database(_) => {
+                "Something went wrong while talking to the database, please try again later."
+                    .to_owned()
+            }
+            CommandError::Discord(_) => {
+                "Something went wrong while talking to Discord, please try again later.".to_owned()
+            }
+            CommandError::Malformed(_) => "This interaction could not be understood.".to_owned(),
+        }
+    }
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CommandError::User(message) => f.write_str(message),
+            CommandError::Database(e) => write!(f, "database error: {e}"),
+            CommandError::Discord(e) => write!(f, "Discord error: {e}"),
+            CommandError::Malformed(what) => write!(f, "malformed interaction: {what}"),
+        }
+    }
+}
+
+impl From<String> for CommandError {
+    fn from(message: String) -> Self {
+        CommandError::User(message)
+    }
+}
+
+impl From<&str> for CommandError {
+    fn from(message: &str) -> Self {
+        CommandError::User(message.to_owned())
+    }
+}
+
+impl From<diesel::result::Error> for CommandError {
+    fn from(e: diesel::result::Error) -> Self {
+        CommandError::Database(e.to_string())
+    }
+}
+
+impl From<PoolError> for CommandError {
+    fn from(e: PoolError) -> Self {
+        CommandError::Database(e.to_string())
+    }
+}
+
+impl From<RepositoryError> for CommandError {
+    fn from(e: RepositoryError) -> Self {
+        CommandError::Database(e.to_string())
+    }
+}
+
+impl From<ImportError> for CommandError {
+    fn from(e: ImportError) -> Self {
+        match e {
+            ImportError::Parse(message) => CommandError::User(message),
+            ImportError::Query(e) => e.into(),
+        }
+    }
+}
+
+impl From<serenity::Error> for CommandError {
+    fn from(e: serenity::Error) -> Self {
+        CommandError::Discord(e)
+    }
+}
+
+/// Logs an error and tells the user about it in an ephemeral message.
+///
+/// If the interaction was already answered, for example by a command that
+/// failed halfway through, the message is sent as a follow-up instead.
+pub async fn report(ctx: &Context, interaction: &Interaction, error: CommandError) {
+    match &error {
+        CommandError::User(message) => warn!("{message}"),
+        e => error!("{e}"),
+    }
+
+    let content = error.user_message();
+
+    macro_rules! respond {
+        ($interaction:expr) => {{
+            let responded = $interaction
+                .create_interaction_response(ctx, |response| {
+                    response
+                        .kind(InteractionResponseType::ChannelMessageWithSource)
+                        .interaction_response_data(|data| data.content(&content).ephemeral(true))
+                })
+                .await;
+            if responded.is_err() {
+                $interaction
+                    .create_followup_message(ctx, |message| {
+                        message.content(&content).ephemeral(true)
+                    })
+                    .await
+                    .map(|_| ())
+            } else {
+                Ok(())
+            }
+        }};
+    }
+
+    let responded = match interaction {
+        Interaction::ApplicationCommand(command) => respond!(command),
+        Interaction::MessageComponent(component) => respond!(component),
+        Interaction::ModalSubmit(modal) => respond!(modal),
+        // autocomplete suggestions have no way to show an error
+        _ => Ok(()),
+    };
+
+    if let Err(why) = responded {
+        error!("Cannot tell the user about an error: {why}");
+    }
+}
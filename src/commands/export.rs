@@ -0,0 +1,98 @@
+use serenity::{
+    async_trait,
+    builder::CreateApplicationCommand,
+    model::prelude::{
+        command::CommandOptionType,
+        interaction::{
+            application_command::ApplicationCommandInteraction, InteractionResponseType,
+        },
+        AttachmentType,
+    },
+    prelude::Context,
+};
+
+use super::{as_boolean, as_string, find_option, CommandError, SlashCommand};
+use crate::export::{export, load_letters, ExportFormat};
+use crate::permissions::ModAction;
+use crate::repository::Repository;
+
+pub const NAME: &str = "export";
+
+pub struct ExportCommand;
+
+#[async_trait]
+impl SlashCommand for ExportCommand {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn action(&self) -> Option<ModAction> {
+        Some(ModAction::Export)
+    }
+
+    fn register<'a>(
+        &self,
+        command: &'a mut CreateApplicationCommand,
+    ) -> &'a mut CreateApplicationCommand {
+        command
+            .name(NAME)
+            .description("exports all letters as a file")
+            .create_option(|option| {
+                option
+                    .name("format")
+                    .description("the file format to export to")
+                    .kind(CommandOptionType::String)
+                    .add_string_choice("JSON", "json")
+                    .add_string_choice("CSV", "csv")
+                    .add_string_choice("Markdown", "markdown")
+                    .required(true)
+            })
+            .create_option(|option| {
+                option
+                    .name("redact")
+                    .description("whether to hide who sent anonymous letters")
+                    .kind(CommandOptionType::Boolean)
+                    .required(false)
+            })
+            .dm_permission(false)
+    }
+
+    async fn run(
+        &self,
+        command: &ApplicationCommandInteraction,
+        ctx: &Context,
+        db: &Repository,
+    ) -> Result<Option<String>, CommandError> {
+        let options = &command.data.options;
+
+        let format = find_option(options, "format")
+            .and_then(|val| as_string(val).ok())
+            .and_then(|name| ExportFormat::from_name(name))
+            .ok_or("Unknown export format")?;
+        let redact = find_option(options, "redact")
+            .and_then(|val| as_boolean(val).ok())
+            .copied()
+            .unwrap_or(false);
+
+        let found_letters = db.query(load_letters).await?;
+        let data = export(&found_letters, format, redact)?;
+
+        command
+            .create_interaction_response(ctx, |response| {
+                response
+                    .kind(InteractionResponseType::ChannelMessageWithSource)
+                    .interaction_response_data(|message| {
+                        message
+                            .content(format!("Exported {} letters", found_letters.len()))
+                            .add_file(AttachmentType::Bytes {
+                                data: data.into(),
+                                filename: format!("letters.{}", format.extension()),
+                            })
+                            .ephemeral(true)
+                    })
+            })
+            .await?;
+
+        Ok(None)
+    }
+}
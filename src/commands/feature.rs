@@ -0,0 +1,61 @@
+use serenity::{
+    model::prelude::interaction::{
+        message_component::MessageComponentInteraction, InteractionResponseType,
+    },
+    prelude::Context,
+};
+use tracing::error;
+
+use super::log_letters::audit_components;
+use super::CommandError;
+use crate::audit::{self, Action};
+use crate::logging;
+use crate::podcast::toggle_featured;
+use crate::repository::{LetterRepository, Repository};
+
+pub async fn handle_button(
+    interaction: &MessageComponentInteraction,
+    ctx: &Context,
+    db: &Repository,
+) -> Result<(), CommandError> {
+    let audit_message = interaction.message.id.to_string();
+    let letter = db
+        .query(move |conn| toggle_featured(conn, &audit_message))
+        .await?;
+    logging::record_letter(letter.id);
+    let featured = Action::Feature
+        .by(interaction.user.id)
+        .on(audit::letter(letter.id))
+        .details(match letter.reading_order {
+            Some(position) => format!("featured at position {position}"),
+            None => "removed from the podcast".to_owned(),
+        });
+    db.record_event(featured).await?;
+    if let Err(why) = interaction
+        .message
+        .channel_id
+        .edit_message(ctx, interaction.message.id, |edit| {
+            edit.components(|components| audit_components(components, letter.featured))
+        })
+        .await
+    {
+        error!("Cannot update audit message: {why}");
+    }
+
+    let content = match letter.reading_order {
+        Some(position) => format!(
+            "Letter #{} will be read on the podcast (position {position})",
+            letter.id
+        ),
+        None => format!("Letter #{} was removed from the podcast", letter.id),
+    };
+
+    interaction
+        .create_interaction_response(ctx, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|data| data.content(content).ephemeral(true))
+        })
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,95 @@
+use serenity::{
+    async_trait,
+    builder::CreateApplicationCommand,
+    model::prelude::{
+        command::CommandOptionType,
+        interaction::application_command::{ApplicationCommandInteraction, CommandDataOptionValue},
+    },
+    prelude::Context,
+};
+
+use super::{as_boolean, find_option, CommandError, SlashCommand};
+use crate::audit::Action;
+use crate::import::{import, ImportFormat};
+use crate::permissions::ModAction;
+use crate::repository::{LetterRepository, Repository};
+
+/// Keeps the reply below Discord's message length limit.
+const MAX_LISTED_ERRORS: usize = 20;
+
+pub const NAME: &str = "import";
+
+pub struct ImportCommand;
+
+#[async_trait]
+impl SlashCommand for ImportCommand {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn action(&self) -> Option<ModAction> {
+        Some(ModAction::Import)
+    }
+
+    fn register<'a>(
+        &self,
+        command: &'a mut CreateApplicationCommand,
+    ) -> &'a mut CreateApplicationCommand {
+        command
+            .name(NAME)
+            .description("imports letters from a JSON or CSV file")
+            .create_option(|option| {
+                option
+                    .name("file")
+                    .description("a .json or .csv file in the same format as /export")
+                    .kind(CommandOptionType::Attachment)
+                    .required(true)
+            })
+            .create_option(|option| {
+                option
+                    .name("dry_run")
+                    .description("only check the file without importing anything")
+                    .kind(CommandOptionType::Boolean)
+                    .required(false)
+            })
+            .dm_permission(false)
+    }
+
+    async fn run(
+        &self,
+        command: &ApplicationCommandInteraction,
+        _ctx: &Context,
+        db: &Repository,
+    ) -> Result<Option<String>, CommandError> {
+        let options = &command.data.options;
+
+        let Some(CommandDataOptionValue::Attachment(file)) = find_option(options, "file") else {
+            return Err("No file found".into());
+        };
+        let dry_run = find_option(options, "dry_run")
+            .and_then(|val| as_boolean(val).ok())
+            .copied()
+            .unwrap_or(false);
+
+        let format = ImportFormat::from_filename(&file.filename)
+            .ok_or("Only .json and .csv files can be imported")?;
+        let data = file
+            .download()
+            .await
+            .map_err(|e| format!("Could not download {}: {e}", file.filename))?;
+
+        let report = db
+            .run(move |conn| import(conn, &data, format, dry_run))
+            .await??;
+        if !dry_run {
+            db.reload_recipients().await?;
+            let imported = Action::Import.by(command.user.id).details(format!(
+                "{} letters and {} recipients from {}",
+                report.letters, report.recipients, file.filename
+            ));
+            db.record_event(imported).await?;
+        }
+
+        Ok(Some(report.summary(MAX_LISTED_ERRORS)))
+    }
+}
@@ -1,41 +1,105 @@
 use serenity::{
-    model::prelude::{component::ButtonStyle, ChannelId, Message},
-    prelude::Context,
+    builder::{CreateComponents, CreateMessage},
+    model::prelude::{component::ButtonStyle, ChannelId, MessageId},
 };
 
 use super::send::ValentineLetter;
+use crate::discord::{build, Discord};
+use crate::filter::neutralize_mentions;
 
 pub async fn log_letter(
-    ctx: &Context,
+    discord: &dyn Discord,
     letter: &ValentineLetter,
+    flags: &[String],
     audit_channel: ChannelId,
-) -> serenity::Result<Message> {
-    ChannelId::send_message(audit_channel, &ctx.http, |m| {
+) -> serenity::Result<MessageId> {
+    let message = build(|m: &mut CreateMessage| {
         m.embed(|embed| {
             embed
-                .title(if letter.anon {
+                .title(neutralize_mentions(&if letter.anon {
                     format!(
                         "Sent anonymously by {} to {}",
                         letter.sender, letter.recipient
                     )
                 } else {
                     format!("Sent by {} to {}", letter.sender, letter.recipient)
-                })
-                .description(&letter.letter)
+                }))
+                .description(neutralize_mentions(&letter.letter))
                 .field("Author ID", &letter.sender_id, true)
-                .footer(|f| f.text("2023 Classroom of the Elite Valentine's Event"))
+                // shown in the time zone of whoever looks at it
+                .field(
+                    "Sent",
+                    format!("<t:{}:f>", letter.sent_at.and_utc().timestamp()),
+                    true,
+                )
+                .field(
+                    "Sent from",
+                    match (&letter.guild_id, &letter.channel_id) {
+                        (Some(_), Some(channel_id)) => format!("<#{channel_id}>"),
+                        _ => "DMs".to_owned(),
+                    },
+                    true,
+                )
+                .footer(|f| f.text("2023 Classroom of the Elite Valentine's Event"));
+            if !flags.is_empty() {
+                embed
+                    .field("⚠ Needs review", flags.join("\n"), false)
+                    .color((255, 165, 0));
+            }
+            embed
+        })
+        .components(|components| audit_components(components, false))
+        .allowed_mentions(|mentions| mentions.empty_parse())
+    });
+    discord.send_message(audit_channel, message).await
+}
+
+/// The moderation buttons below a logged letter.
+pub fn audit_components(
+    components: &mut CreateComponents,
+    featured: bool,
+) -> &mut CreateComponents {
+    components.create_action_row(|row| {
+        row.create_button(|button| {
+            button
+                .custom_id("delete_letter")
+                .emoji('🗑')
+                .style(ButtonStyle::Danger)
+                .label("Delete")
+        })
+        .create_button(|button| {
+            if featured {
+                button
+                    .custom_id("feature_letter")
+                    .emoji('🎙')
+                    .style(ButtonStyle::Secondary)
+                    .label("Remove from podcast")
+            } else {
+                button
+                    .custom_id("feature_letter")
+                    .emoji('🎙')
+                    .style(ButtonStyle::Success)
+                    .label("Feature on podcast")
+            }
+        })
+        .create_button(|button| {
+            button
+                .custom_id("vote_up")
+                .emoji('👍')
+                .style(ButtonStyle::Secondary)
+        })
+        .create_button(|button| {
+            button
+                .custom_id("vote_down")
+                .emoji('👎')
+                .style(ButtonStyle::Secondary)
         })
-        .components(|components| {
-            components.create_action_row(|row| {
-                row.create_button(|button| {
-                    button
-                        .custom_id("delete_letter")
-                        .emoji('🗑')
-                        .style(ButtonStyle::Danger)
-                        .label("Delete")
-                })
-            })
+        .create_button(|button| {
+            button
+                .custom_id("block_sender")
+                .emoji('⛔')
+                .style(ButtonStyle::Danger)
+                .label("Block sender")
         })
     })
-    .await
 }
@@ -1,12 +1,106 @@
 pub mod add_recipient;
+pub mod allow_letters;
+pub mod auditlog;
+pub mod block;
+pub mod card;
 pub mod delete;
+mod error;
+pub mod export;
+pub mod feature;
+pub mod import;
 pub mod log_letters;
+pub mod podcast_script;
 pub mod publish;
+pub mod reading_order;
+pub mod registration;
 pub mod send;
-pub mod allow_letters;
+pub mod shortlist;
+mod slash_command;
+pub mod stats;
+pub mod unblock;
+pub mod vote;
+
+pub use error::{report, CommandError};
+pub use slash_command::{Registry, SlashCommand};
+
+use std::env;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+use serenity::model::prelude::interaction::application_command::{
+    CommandDataOption, CommandDataOptionValue,
+};
+use serenity::model::prelude::{ChannelId, User};
+use tracing::warn;
 
-use serenity::model::prelude::interaction::application_command::CommandDataOptionValue;
-pub fn as_string(optionval: &CommandDataOptionValue) -> Result<&String, ()> {
+use crate::cooldown::Cooldown;
+use crate::eligibility::Eligibility;
+use crate::filter::ContentFilter;
+
+/// All commands, in the order they are registered in.
+pub fn registry(
+    dashboard: stats::Dashboard,
+    filter: ContentFilter,
+    cooldown: Cooldown,
+    eligibility: Eligibility,
+) -> Registry {
+    let letters_allowed = Arc::new(AtomicBool::new(true));
+    let audit_channel = audit_channel();
+
+    Registry::new(vec![
+        Box::new(send::SendLetterCommand {
+            letters_allowed: letters_allowed.clone(),
+            audit_channel,
+            filter,
+            cooldown,
+            eligibility,
+        }),
+        Box::new(publish::PublishCommand),
+        Box::new(add_recipient::AddRecipientCommand),
+        Box::new(allow_letters::AllowLettersCommand { letters_allowed }),
+        Box::new(import::ImportCommand),
+        Box::new(export::ExportCommand),
+        Box::new(reading_order::ReadingOrderCommand),
+        Box::new(podcast_script::PodcastScriptCommand),
+        Box::new(shortlist::ShortlistCommand),
+        Box::new(card::CardCommand),
+        Box::new(stats::StatsCommand {
+            dashboard,
+            audit_channel,
+        }),
+        Box::new(block::BlockCommand),
+        Box::new(unblock::UnblockCommand),
+        Box::new(auditlog::AuditLogCommand),
+    ])
+}
+
+/// The channel letters are logged in for moderation, from `AUDIT_CHANNEL_ID`.
+fn audit_channel() -> Option<ChannelId> {
+    match env::var("AUDIT_CHANNEL_ID")
+        .map_err(|e| e.to_string())
+        .and_then(|id_as_str| id_as_str.parse::<u64>().map_err(|e| e.to_string()))
+    {
+        Ok(id) => Some(ChannelId(id)),
+        Err(e) => {
+            warn!("letters will not be logged: no audit channel specified!\n{e}");
+            None
+        }
+    }
+}
+
+/// Looks an option up by name, for commands with optional options where the
+/// position of an option is not fixed.
+pub(crate) fn find_option<'a>(
+    options: &'a [CommandDataOption],
+    name: &str,
+) -> Option<&'a CommandDataOptionValue> {
+    options
+        .iter()
+        .find(|option| option.name == name)
+        .and_then(|option| option.resolved.as_ref())
+}
+
+pub(crate) fn as_string(optionval: &CommandDataOptionValue) -> Result<&String, ()> {
     if let CommandDataOptionValue::String(stringval) = optionval {
         Ok(stringval)
     } else {
@@ -14,10 +108,18 @@ pub fn as_string(optionval: &CommandDataOptionValue) -> Result<&String, ()> {
     }
 }
 
-pub fn as_boolean(optionval: &CommandDataOptionValue) -> Result<&bool, ()> {
+pub(crate) fn as_boolean(optionval: &CommandDataOptionValue) -> Result<&bool, ()> {
     if let CommandDataOptionValue::Boolean(val) = optionval {
         Ok(val)
     } else {
         Err(())
     }
 }
+
+pub(crate) fn as_user(optionval: &CommandDataOptionValue) -> Result<&User, ()> {
+    if let CommandDataOptionValue::User(user, _) = optionval {
+        Ok(user)
+    } else {
+        Err(())
+    }
+}
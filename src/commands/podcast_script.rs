@@ -0,0 +1,72 @@
+use serenity::{
+    async_trait,
+    builder::CreateApplicationCommand,
+    model::prelude::{
+        interaction::{
+            application_command::ApplicationCommandInteraction, InteractionResponseType,
+        },
+        AttachmentType,
+    },
+    prelude::Context,
+};
+
+use super::{CommandError, SlashCommand};
+use crate::permissions::ModAction;
+use crate::podcast::{load_featured, script};
+use crate::repository::Repository;
+
+pub const NAME: &str = "podcast_script";
+
+pub struct PodcastScriptCommand;
+
+#[async_trait]
+impl SlashCommand for PodcastScriptCommand {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn action(&self) -> Option<ModAction> {
+        Some(ModAction::Review)
+    }
+
+    fn register<'a>(
+        &self,
+        command: &'a mut CreateApplicationCommand,
+    ) -> &'a mut CreateApplicationCommand {
+        command
+            .name(NAME)
+            .description("creates a reading script of the letters featured on the podcast")
+            .dm_permission(false)
+    }
+
+    async fn run(
+        &self,
+        command: &ApplicationCommandInteraction,
+        ctx: &Context,
+        db: &Repository,
+    ) -> Result<Option<String>, CommandError> {
+        let featured = db.query(load_featured).await?;
+
+        if featured.is_empty() {
+            return Ok(Some("No letters are featured yet.".to_owned()));
+        }
+
+        command
+            .create_interaction_response(ctx, |response| {
+                response
+                    .kind(InteractionResponseType::ChannelMessageWithSource)
+                    .interaction_response_data(|message| {
+                        message
+                            .content(format!("Reading script for {} letters", featured.len()))
+                            .add_file(AttachmentType::Bytes {
+                                data: script(&featured).into_bytes().into(),
+                                filename: "podcast_script.md".to_owned(),
+                            })
+                            .ephemeral(true)
+                    })
+            })
+            .await?;
+
+        Ok(None)
+    }
+}
@@ -1,28 +1,40 @@
 use diesel::prelude::*;
-use diesel::SqliteConnection;
-use serenity::builder::CreateEmbed;
+use serenity::builder::{
+    CreateEmbed, CreateInteractionResponse, CreateMessage, EditInteractionResponse,
+};
 
 use serenity::model::prelude::interaction::InteractionResponseType;
 use serenity::{
+    async_trait,
     builder::CreateApplicationCommand,
-    model::{
-        prelude::interaction::application_command::ApplicationCommandInteraction, Permissions,
+    model::prelude::{
+        command::CommandOptionType,
+        interaction::application_command::ApplicationCommandInteraction, AttachmentType,
     },
     prelude::Context,
 };
 use tokio::time::{sleep, Duration};
-
+use tracing::{debug, info};
+
+use super::{as_boolean, find_option, CommandError, SlashCommand};
+use crate::audit::{self, Action};
+use crate::card::render_card_in_background;
+use crate::discord::{build, Discord};
+use crate::filter::neutralize_mentions;
+use crate::metrics;
 use crate::model::Letter;
-use crate::schema::letters::dsl::letters;
+use crate::permissions::ModAction;
+use crate::repository::{LetterRepository, Repository};
+use crate::schema::letters::dsl::{created_at, id, letters};
 
 impl Letter {
     fn build_embed<'a>(&self, e: &'a mut CreateEmbed) -> &'a mut CreateEmbed {
-        e.title(if self.anon {
-            format!("To {}", self.recipient.clone())
+        e.title(neutralize_mentions(&if self.anon {
+            format!("To {}", self.recipient)
         } else {
-            format!("From {} to {}", self.sender.clone(), self.recipient.clone())
-        })
-        .description(self.content.clone())
+            format!("From {} to {}", self.sender, self.recipient)
+        }))
+        .description(neutralize_mentions(&self.content))
         .footer(|f| f.text("2023 Classroom of the Elite Valentine's Event"))
         .colour({
             use random_color::{Color, RandomColor};
@@ -33,22 +45,78 @@ impl Letter {
     }
 }
 
-pub async fn run(
+pub const NAME: &str = "publish";
+
+pub struct PublishCommand;
+
+#[async_trait]
+impl SlashCommand for PublishCommand {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn action(&self) -> Option<ModAction> {
+        Some(ModAction::Publish)
+    }
+
+    fn register<'a>(
+        &self,
+        command: &'a mut CreateApplicationCommand,
+    ) -> &'a mut CreateApplicationCommand {
+        command
+            .name(NAME)
+            .description("sends all letters to a channel")
+            .create_option(|option| {
+                option
+                    .name("cards")
+                    .description("whether to attach every letter as an image")
+                    .kind(CommandOptionType::Boolean)
+                    .required(false)
+            })
+            .dm_permission(false)
+    }
+
+    async fn run(
+        &self,
+        command: &ApplicationCommandInteraction,
+        ctx: &Context,
+        db: &Repository,
+    ) -> Result<Option<String>, CommandError> {
+        publish(command, &ctx.http, db).await?;
+        Ok(None)
+    }
+}
+
+/// Sends every letter to the channel the command was used in, spread over at
+/// most ten minutes.
+pub async fn publish(
     command: &ApplicationCommandInteraction,
-    ctx: &Context,
-    db_conn: &mut SqliteConnection,
-) -> Result<Option<String>, String> {
+    discord: &dyn Discord,
+    db: &Repository,
+) -> Result<(), CommandError> {
     // first, deferred reply to be allowed to take longer:
-    command
-        .create_interaction_response(ctx, |response| {
-            response.kind(InteractionResponseType::DeferredChannelMessageWithSource)
+    let deferred = build(|response: &mut CreateInteractionResponse| {
+        response.kind(InteractionResponseType::DeferredChannelMessageWithSource)
+    });
+    discord
+        .respond(command.id, &command.token, deferred)
+        .await?;
+
+    let with_cards = find_option(&command.data.options, "cards")
+        .and_then(|val| as_boolean(val).ok())
+        .copied()
+        .unwrap_or(false);
+
+    let found_letters = db
+        .query(|conn| {
+            letters
+                .order((created_at.asc(), id.asc()))
+                .load::<Letter>(conn)
         })
-        .await
-        .map_err(|e| format!("Error while sending a response:\n ```{e:?}```"))?;
-
-    let found_letters = letters
-        .load::<Letter>(db_conn)
-        .map_err(|e| format!("Error while connecting to database:\n ```{e:?}```"))?;
+        .await?;
+    if found_letters.is_empty() {
+        return Err("There are no letters to publish".into());
+    }
 
     const MAX_RUNTIME: Duration = Duration::from_secs(60 * 10);
     const MAX_DELAY_PER_LETTER: Duration = Duration::from_secs(5);
@@ -57,39 +125,65 @@ pub async fn run(
         (MAX_RUNTIME.as_millis() / found_letters.len() as u128) as u64,
     ));
 
+    let published = Action::Publish
+        .by(command.user.id)
+        .on(audit::channel(command.channel_id))
+        .details(format!(
+            "{} letters{}",
+            found_letters.len(),
+            if with_cards { " with cards" } else { "" }
+        ));
+    db.record_event(published).await?;
+
+    metrics::PUBLISH_TOTAL.set(found_letters.len() as i64);
+    metrics::PUBLISH_SENT.set(0);
+    info!("Publishing {} letters", found_letters.len());
+
     let channel_id = command.channel_id;
 
-    let typing = channel_id
-        .start_typing(&ctx.http)
-        .map_err(|e| format!("Error sending typing event.\n ```{e:?}```"))?;
+    let typing = discord.start_typing(channel_id)?;
 
     for letter in found_letters {
         // wait a bit
         sleep(max_delay).await;
         // send embed and stop typing
 
-        let ret = channel_id
-            .send_message(ctx, |m| m.embed(|embed| letter.build_embed(embed)))
-            .await
-            .map_err(|e| format!("Error sending a message:\n```{e:?}```"))?;
-
-        dbg!(ret);
+        let card = if with_cards {
+            Some(render_card_in_background(&letter).await?)
+        } else {
+            None
+        };
+        let filename = format!("letter-{}.png", letter.id);
+
+        let message = build(|m: &mut CreateMessage| {
+            m.allowed_mentions(|mentions| mentions.empty_parse());
+            match card {
+                Some(card) => m
+                    .embed(|embed| letter.build_embed(embed).attachment(&filename))
+                    .add_file(AttachmentType::Bytes {
+                        data: card.into(),
+                        filename: filename.clone(),
+                    }),
+                None => m.embed(|embed| letter.build_embed(embed)),
+            }
+        });
+        let ret = discord.send_message(channel_id, message).await?;
+        metrics::PUBLISH_SENT.inc();
+
+        debug!(letter_id = letter.id, message_id = %ret, "Published letter");
     }
 
-    let _ = typing.stop();
+    typing.stop();
 
-    command
-        .edit_original_interaction_response(ctx, |edit| edit.content("Done"))
-        .await
-        .unwrap();
-
-    Ok(None)
-}
+    discord
+        .edit_response(
+            &command.token,
+            build(|edit: &mut EditInteractionResponse| {
+                edit.content("Done")
+                    .allowed_mentions(|mentions| mentions.empty_parse())
+            }),
+        )
+        .await?;
 
-pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
-    command
-        .name("publish")
-        .description("sends all letters to a channel")
-        .dm_permission(false)
-        .default_member_permissions(Permissions::MANAGE_MESSAGES)
+    Ok(())
 }
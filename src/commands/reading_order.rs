@@ -0,0 +1,133 @@
+use serenity::{
+    async_trait,
+    builder::CreateApplicationCommand,
+    model::prelude::{
+        command::CommandOptionType,
+        interaction::{
+            application_command::{ApplicationCommandInteraction, CommandDataOptionValue},
+            message_component::MessageComponentInteraction,
+        },
+    },
+    prelude::Context,
+};
+
+use super::{find_option, CommandError, SlashCommand};
+use crate::audit::{self, Action};
+use crate::model::Letter;
+use crate::permissions::ModAction;
+use crate::podcast::{load_featured, move_letter, read_time, word_count};
+use crate::repository::{LetterRepository, Repository};
+
+pub const NAME: &str = "reading_order";
+
+pub struct ReadingOrderCommand;
+
+#[async_trait]
+impl SlashCommand for ReadingOrderCommand {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn action(&self) -> Option<ModAction> {
+        Some(ModAction::Review)
+    }
+
+    fn register<'a>(
+        &self,
+        command: &'a mut CreateApplicationCommand,
+    ) -> &'a mut CreateApplicationCommand {
+        command
+            .name(NAME)
+            .description("shows or changes the order featured letters are read in")
+            .create_option(|option| {
+                option
+                    .name("letter")
+                    .description("ID of the featured letter to move")
+                    .kind(CommandOptionType::Integer)
+                    .min_int_value(1)
+                    .required(false)
+            })
+            .create_option(|option| {
+                option
+                    .name("position")
+                    .description("where to move the letter to, starting at 1")
+                    .kind(CommandOptionType::Integer)
+                    .min_int_value(1)
+                    .required(false)
+            })
+            .dm_permission(false)
+    }
+
+    async fn run(
+        &self,
+        command: &ApplicationCommandInteraction,
+        _ctx: &Context,
+        db: &Repository,
+    ) -> Result<Option<String>, CommandError> {
+        let options = &command.data.options;
+        let as_integer = |name| match find_option(options, name) {
+            Some(CommandDataOptionValue::Integer(val)) => Some(*val),
+            _ => None,
+        };
+
+        let order = match (as_integer("letter"), as_integer("position")) {
+            (Some(letter), Some(position)) => {
+                let letter = i32::try_from(letter).map_err(|_| "Letter ID is out of range")?;
+                let position = usize::try_from(position).map_err(|_| "Position is out of range")?;
+                let order = db
+                    .run(move |conn| move_letter(conn, letter, position))
+                    .await?
+                    .map_err(|e| match e {
+                        diesel::result::Error::NotFound => {
+                            CommandError::User(format!("Letter #{letter} is not featured"))
+                        }
+                        e => e.into(),
+                    })?;
+                let moved = Action::ReadingOrder
+                    .by(command.user.id)
+                    .on(audit::letter(letter))
+                    .details(format!("moved to position {position}"));
+                db.record_event(moved).await?;
+                order
+            }
+            (None, None) => db.query(load_featured).await?,
+            _ => return Err("Specify both a letter and a position to move a letter".into()),
+        };
+
+        Ok(Some(describe_order(&order)))
+    }
+
+    /// The button on audit messages that adds a letter to the podcast.
+    fn custom_ids(&self) -> &'static [&'static str] {
+        &["feature_letter"]
+    }
+
+    async fn component(
+        &self,
+        interaction: &MessageComponentInteraction,
+        ctx: &Context,
+        db: &Repository,
+    ) -> Result<(), CommandError> {
+        super::feature::handle_button(interaction, ctx, db).await
+    }
+}
+
+fn describe_order(order: &[Letter]) -> String {
+    if order.is_empty() {
+        return "No letters are featured yet.".to_owned();
+    }
+
+    order
+        .iter()
+        .zip(1..)
+        .map(|(letter, position)| {
+            format!(
+                "{position}. Letter #{} to {} ({})",
+                letter.id,
+                letter.recipient,
+                read_time(word_count(&letter.content))
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -0,0 +1,209 @@
+use std::env;
+
+use serde_json::{Map, Value};
+use serenity::{
+    builder::CreateApplicationCommands,
+    http::Http,
+    model::{application::command::Command, id::GuildId},
+};
+use tracing::{info, warn};
+
+use super::Registry;
+use crate::permissions::Access;
+
+/// Where the slash commands are registered.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Scope {
+    /// Everywhere the bot is. Changes can take a while to show up.
+    Global,
+    /// Only in one guild, where changes show up right away. Meant for testing
+    /// with a bot of its own.
+    Guild(GuildId),
+}
+
+/// The scope commands are registered in, and the one they are removed from so
+/// they do not show up twice after switching between the two.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Scopes {
+    pub registered: Scope,
+    pub cleared: Option<Scope>,
+}
+
+impl Scopes {
+    /// Reads `COMMAND_SCOPE`, which is `global` unless set to `guild`, and
+    /// `COMMAND_GUILD_ID`, the guild commands are registered in for testing.
+    /// Commands in that guild are removed when registering globally, and
+    /// global commands when registering in the guild.
+    pub fn from_env() -> Result<Self, String> {
+        let guild = match env::var("COMMAND_GUILD_ID") {
+            Ok(id) if !id.is_empty() => {
+                Some(GuildId(id.parse().map_err(|e| {
+                    format!("COMMAND_GUILD_ID must be an integer: {e}")
+                })?))
+            }
+            _ => None,
+        };
+
+        match env::var("COMMAND_SCOPE").as_deref() {
+            Err(_) | Ok("") | Ok("global") => Ok(Scopes {
+                registered: Scope::Global,
+                cleared: guild.map(Scope::Guild),
+            }),
+            Ok("guild") => Ok(Scopes {
+                registered: Scope::Guild(
+                    guild.ok_or("COMMAND_SCOPE=guild requires COMMAND_GUILD_ID")?,
+                ),
+                cleared: Some(Scope::Global),
+            }),
+            Ok(other) => Err(format!(
+                "COMMAND_SCOPE must be either global or guild, not {other}"
+            )),
+        }
+    }
+}
+
+/// How the registered commands differ from the ones the bot has.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct CommandDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<String>,
+}
+
+impl CommandDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Compares commands as registered with Discord to the ones about to be
+/// registered, both as the JSON Discord uses.
+pub fn diff(registered: &[Value], wanted: &[Value]) -> CommandDiff {
+    let named = |commands: &'_ [Value], name: &Value| {
+        commands
+            .iter()
+            .find(|command| command["name"] == *name)
+            .cloned()
+    };
+    let name = |command: &Value| command["name"].as_str().unwrap_or_default().to_owned();
+
+    let mut diff = CommandDiff::default();
+    for new in wanted {
+        match named(registered, &new["name"]) {
+            None => diff.added.push(name(new)),
+            Some(old) if comparable(&old) != comparable(new) => diff.changed.push(name(new)),
+            Some(_) => {}
+        }
+    }
+    for old in registered {
+        if named(wanted, &old["name"]).is_none() {
+            diff.removed.push(name(old));
+        }
+    }
+    diff
+}
+
+/// The parts of a command we set, with Discord's defaults filled in.
+fn comparable(command: &Value) -> Value {
+    serde_json::json!({
+        "type": command.get("type").cloned().unwrap_or(Value::from(1)),
+        "description": command["description"],
+        "options": match command.get("options") {
+            Some(Value::Array(options)) if !options.is_empty() => {
+                without_defaults(Value::Array(options.clone()))
+            }
+            _ => Value::Null,
+        },
+        "default_member_permissions": command["default_member_permissions"],
+        "dm_permission": command.get("dm_permission").cloned().unwrap_or(Value::Bool(true)),
+    })
+}
+
+/// Drops unset option fields, which Discord returns as `false` or empty lists
+/// but which the builders leave out.
+fn without_defaults(value: Value) -> Value {
+    match value {
+        Value::Array(values) => Value::Array(values.into_iter().map(without_defaults).collect()),
+        Value::Object(fields) => Value::Object(
+            fields
+                .into_iter()
+                .filter(|(_, value)| {
+                    !matches!(value, Value::Null | Value::Bool(false))
+                        && !matches!(value, Value::Array(values) if values.is_empty())
+                })
+                .map(|(key, value)| (key, without_defaults(value)))
+                .collect::<Map<_, _>>(),
+        ),
+        value => value,
+    }
+}
+
+/// Replaces the commands registered in `scopes.registered` with the ones in
+/// `registry`, which also removes commands the bot no longer has, removes any
+/// commands from `scopes.cleared` and logs what changed.
+pub async fn sync(
+    http: &Http,
+    registry: &Registry,
+    access: &Access,
+    scopes: Scopes,
+) -> serenity::Result<()> {
+    let mut wanted = CreateApplicationCommands::default();
+    registry.register_all(&mut wanted, access);
+
+    if let Some(scope) = scopes.cleared {
+        let stale = registered(http, scope).await?;
+        if !stale.is_empty() {
+            for command in &stale {
+                warn!(?scope, "Removing slash command /{}", command.name);
+            }
+            set(http, scope, |commands| commands).await?;
+        }
+    }
+
+    let scope = scopes.registered;
+    let registered = registered(http, scope)
+        .await?
+        .iter()
+        .map(serde_json::to_value)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let changes = diff(&registered, &wanted.0);
+    if changes.is_empty() {
+        info!(?scope, "{} slash commands are up to date", registered.len());
+        return Ok(());
+    }
+    for name in &changes.added {
+        info!(?scope, "Adding slash command /{name}");
+    }
+    for name in &changes.changed {
+        info!(?scope, "Updating slash command /{name}");
+    }
+    for name in &changes.removed {
+        warn!(?scope, "Removing stale slash command /{name}");
+    }
+
+    let now = set(http, scope, |commands| {
+        registry.register_all(commands, access)
+    })
+    .await?;
+    info!(?scope, "{} slash commands registered", now.len());
+    Ok(())
+}
+
+async fn registered(http: &Http, scope: Scope) -> serenity::Result<Vec<Command>> {
+    match scope {
+        Scope::Global => Command::get_global_application_commands(http).await,
+        Scope::Guild(guild_id) => guild_id.get_application_commands(http).await,
+    }
+}
+
+/// Replaces every command in `scope` with the ones `f` builds.
+async fn set<F>(http: &Http, scope: Scope, f: F) -> serenity::Result<Vec<Command>>
+where
+    F: FnOnce(&mut CreateApplicationCommands) -> &mut CreateApplicationCommands,
+{
+    match scope {
+        Scope::Global => Command::set_global_application_commands(http, f).await,
+        Scope::Guild(guild_id) => guild_id.set_application_commands(http, f).await,
+    }
+}
@@ -1,8 +1,11 @@
-use std::env;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
-use diesel::prelude::*;
+use chrono::{NaiveDateTime, Utc};
+use tracing::info;
 
 use serenity::{
+    async_trait,
     builder::CreateApplicationCommand,
     model::{
         prelude::{
@@ -10,8 +13,9 @@ use serenity::{
             interaction::{
                 application_command::ApplicationCommandInteraction,
                 autocomplete::AutocompleteInteraction,
+                message_component::MessageComponentInteraction, modal::ModalSubmitInteraction,
             },
-            ChannelId, Message,
+            ChannelId,
         },
         Permissions,
     },
@@ -19,170 +23,280 @@ use serenity::{
 };
 
 use crate::commands::log_letters::log_letter;
-
-use super::{as_boolean, as_string};
-
-pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
-    command
-        .name("sendletter")
-        .description("Send a letter to your valentine! (max 2 letters allowed)")
-        .create_option(|option| {
-            option
-                .name("recipient")
-                .description("The mod or heroine whom you want to send a valentine's letter to")
-                .kind(CommandOptionType::String)
-                .min_length(1)
-                .max_length(20)
-                .required(true)
-                .set_autocomplete(true)
-        })
-        .create_option(|option| {
-            option
-                .name("letter")
-                .description("The letter that you want to send to this person!")
-                .kind(CommandOptionType::String)
-                .min_length(100)
-                .required(true)
-        })
-        .create_option(|option| {
-            option
-                .name("anonymous")
-                .description("Do you want to send this message anonymously?")
-                .kind(CommandOptionType::Boolean)
-                .required(true)
-        })
-        .dm_permission(true)
-        .default_member_permissions(Permissions::SEND_MESSAGES)
+use crate::cooldown::Cooldown;
+use crate::discord::Discord;
+use crate::duplicates::Duplicate;
+use crate::eligibility::Eligibility;
+use crate::filter::ContentFilter;
+use crate::permissions::ModAction;
+use crate::repository::{LetterRepository, Repository};
+use crate::{logging, metrics};
+
+use super::{as_boolean, as_string, CommandError, SlashCommand};
+
+pub const RECIPIENT_MAX_LENGTH: u16 = 20;
+pub const LETTER_MIN_LENGTH: u16 = 100;
+
+pub const NAME: &str = "sendletter";
+
+pub struct SendLetterCommand {
+    /// Shared with `/allow_letters`.
+    pub letters_allowed: Arc<AtomicBool>,
+    /// Where letters are logged for moderation.
+    pub audit_channel: Option<ChannelId>,
+    pub filter: ContentFilter,
+    pub cooldown: Cooldown,
+    pub eligibility: Eligibility,
 }
 
-fn user_can_send_letter(
-    conn: &mut SqliteConnection,
-    letter: &ValentineLetter,
-) -> Result<bool, DatabaseProblem> {
-    use crate::schema::letters::dsl::{letters, sender};
-
-    let letter_count: i64 = letters
-        .filter(sender.eq(&letter.sender))
-        .count()
-        .get_result(conn)
-        .map_err(|_| DatabaseProblem)?;
-
-    if letter_count >= 2 {
-        Ok(false)
-    } else {
-        Ok(true)
+impl SendLetterCommand {
+    /// Checks and stores the letter, and logs it in the audit channel.
+    /// Returns the answer for the sender.
+    pub async fn send(
+        &self,
+        command: &ApplicationCommandInteraction,
+        discord: &dyn Discord,
+        db: &dyn LetterRepository,
+    ) -> Result<String, CommandError> {
+        if !self.letters_allowed.load(Ordering::SeqCst) {
+            return forbidden().await;
+        }
+
+        let mut letter: ValentineLetter = command.try_into().map_err(|e| {
+            metrics::LETTERS_REJECTED
+                .with_label_values(&["invalid"])
+                .inc();
+            format!("Error while parsing arguments: {e}")
+        })?;
+
+        if db.is_blocked(letter.sender_id.clone()).await? {
+            metrics::LETTERS_REJECTED
+                .with_label_values(&["blocked"])
+                .inc();
+            return Err("You are not allowed to send letters.".into());
+        }
+
+        if let Err(why) = self.eligibility.check(command, discord).await? {
+            metrics::LETTERS_REJECTED
+                .with_label_values(&["ineligible"])
+                .inc();
+            return Err(why.into());
+        }
+
+        let screened = self.filter.screen(&letter.letter).map_err(|reason| {
+            metrics::LETTERS_REJECTED
+                .with_label_values(&["filtered"])
+                .inc();
+            format!("Your letter was not accepted because it {reason}.")
+        })?;
+        letter.letter = screened.letter;
+        // removing mentions can make it too short
+        letter.validate().map_err(|e| {
+            metrics::LETTERS_REJECTED
+                .with_label_values(&["invalid"])
+                .inc();
+            format!("Your letter was not accepted without its mentions: {e}.")
+        })?;
+
+        if !db.can_send_letter(letter.sender.clone()).await? {
+            metrics::LETTERS_REJECTED
+                .with_label_values(&["quota"])
+                .inc();
+            return Ok("You have already sent two messages.".to_owned());
+        }
+
+        if let Err(wait) = self.cooldown.start(&letter.sender_id) {
+            metrics::LETTERS_REJECTED
+                .with_label_values(&["cooldown"])
+                .inc();
+            let seconds = wait.as_secs_f64().ceil();
+            return Err(
+                format!("Please wait {seconds} seconds before sending another letter.").into(),
+            );
+        }
+
+        // the cooldown only counts letters that were recorded
+        let sender_id = letter.sender_id.clone();
+        let recorded = self.record(letter, screened.flags, discord, db).await;
+        if recorded.is_err() {
+            self.cooldown.cancel(&sender_id);
+        }
+        recorded
     }
-}
 
-fn add_letter_to_user(
-    conn: &mut SqliteConnection,
-    letter: &ValentineLetter,
-    log_message: Option<&Message>,
-) -> Result<(), DatabaseProblem> {
-    use crate::model::NewLetter;
-    use crate::schema::letters::dsl::letters;
-
-    let letter = NewLetter {
-        sender: &letter.sender,
-        recipient: &letter.recipient,
-        anon: letter.anon,
-        content: &letter.letter,
-        message_id: log_message.map(|msg| msg.id.to_string()),
-        sender_id: &letter.sender_id,
-    };
-
-    diesel::insert_into(letters)
-        .values(&letter)
-        .execute(conn)
-        .map_err(|_| DatabaseProblem)?;
+    /// Stores the letter and logs it, once it passed every check.
+    async fn record(
+        &self,
+        letter: ValentineLetter,
+        mut flags: Vec<String>,
+        discord: &dyn Discord,
+        db: &dyn LetterRepository,
+    ) -> Result<String, CommandError> {
+        let duplicate = db
+            .find_duplicate(letter.sender_id.clone(), letter.letter.clone())
+            .await?;
+        if let Some(duplicate) = duplicate {
+            metrics::LETTERS_DUPLICATE.inc();
+            flags.push(duplicate.to_string());
+        }
+
+        if !flags.is_empty() {
+            metrics::LETTERS_FLAGGED.inc();
+        }
+
+        let log_message = match self.audit_channel {
+            Some(log_channel) => Some(log_letter(discord, &letter, &flags, log_channel).await?),
+            None => None,
+        };
 
-    Ok(())
+        let letter_id = db
+            .add_letter(letter, log_message.map(|id| id.to_string()))
+            .await?;
+        logging::record_letter(letter_id);
+        metrics::LETTERS_SUBMITTED.inc();
+        info!("Letter recorded");
+
+        let thanks = "Thank you for your message, it has been recorded.".to_owned();
+        let sent_by = match duplicate {
+            Some(Duplicate { own: true, .. }) => "you",
+            Some(Duplicate { own: false, .. }) => "someone else",
+            None => return Ok(thanks),
+        };
+        Ok(format!(
+            "{thanks} It looks a lot like a letter {sent_by} already sent, \
+                    so a moderator will have a look at it."
+        ))
+    }
 }
 
-pub async fn run(
-    command: &ApplicationCommandInteraction,
-    ctx: &Context,
-    db_conn: &mut SqliteConnection,
-) -> Result<Option<String>, String> {
-    let letter: ValentineLetter = command
-        .try_into()
-        .map_err(|e| format!("Error while parsing arguments: {e:?}"))?;
-
-    let can_send = user_can_send_letter(db_conn, &letter)
-        .map_err(|_| "Something went very wrong.".to_owned())?;
-
-    Ok(Some(if can_send {
-        let log_message = if let Some(log_channel) = env::var("AUDIT_CHANNEL_ID")
-            .map_err(|e| e.to_string())
-            .and_then(|id_as_str| id_as_str.parse::<u64>().map_err(|e| e.to_string()))
-            .map(ChannelId)
-            .map_or_else(
-                |e| {
-                    println!("letter not logged: no audit channel specified!\n{e}");
-                    None
-                },
-                Some,
-            )
-        {
-            Some(
-                log_letter(ctx, &letter, log_channel)
-                    .await
-                    .map_err(|_| "Something went wrong")?
-                )
-        } else {
-            None
-        };
+#[async_trait]
+impl SlashCommand for SendLetterCommand {
+    fn name(&self) -> &'static str {
+        NAME
+    }
 
-        add_letter_to_user(db_conn, &letter, log_message.as_ref())
-            .map_err(|_| "Something went very wrong.".to_owned())?;
+    /// Anyone may send letters, but only moderators may delete them.
+    fn action(&self) -> Option<ModAction> {
+        None
+    }
+
+    fn component_action(&self, _custom_id: &str) -> Option<ModAction> {
+        Some(ModAction::Delete)
+    }
+
+    fn register<'a>(
+        &self,
+        command: &'a mut CreateApplicationCommand,
+    ) -> &'a mut CreateApplicationCommand {
+        command
+            .name(NAME)
+            .description("Send a letter to your valentine! (max 2 letters allowed)")
+            .create_option(|option| {
+                option
+                    .name("recipient")
+                    .description("The mod or heroine whom you want to send a valentine's letter to")
+                    .kind(CommandOptionType::String)
+                    .min_length(1)
+                    .max_length(RECIPIENT_MAX_LENGTH)
+                    .required(true)
+                    .set_autocomplete(true)
+            })
+            .create_option(|option| {
+                option
+                    .name("letter")
+                    .description("The letter that you want to send to this person!")
+                    .kind(CommandOptionType::String)
+                    .min_length(LETTER_MIN_LENGTH)
+                    .required(true)
+            })
+            .create_option(|option| {
+                option
+                    .name("anonymous")
+                    .description("Do you want to send this message anonymously?")
+                    .kind(CommandOptionType::Boolean)
+                    .required(true)
+            })
+            .dm_permission(true)
+            .default_member_permissions(Permissions::SEND_MESSAGES)
+    }
+
+    async fn run(
+        &self,
+        command: &ApplicationCommandInteraction,
+        ctx: &Context,
+        db: &Repository,
+    ) -> Result<Option<String>, CommandError> {
+        self.send(command, &ctx.http, db).await.map(Some)
+    }
 
-        "Thank you for your message, it has been recorded.".to_owned()
-    } else {
-        "You have already sent two messages.".to_owned()
-    }))
+    /// The delete button on audit messages and the modal confirming it.
+    fn custom_ids(&self) -> &'static [&'static str] {
+        &["delete_letter", "delete_modal"]
+    }
+
+    async fn component(
+        &self,
+        interaction: &MessageComponentInteraction,
+        ctx: &Context,
+        _db: &Repository,
+    ) -> Result<(), CommandError> {
+        super::delete::handle_button(interaction, &ctx.http).await
+    }
+
+    async fn modal(
+        &self,
+        interaction: &mut ModalSubmitInteraction,
+        ctx: &Context,
+        db: &Repository,
+    ) -> Result<(), CommandError> {
+        super::delete::handle_modal(interaction, &ctx.http, db).await
+    }
+
+    async fn autocomplete(
+        &self,
+        interaction: &AutocompleteInteraction,
+        ctx: &Context,
+        db: &Repository,
+    ) -> Result<(), CommandError> {
+        complete(interaction, ctx, db).await
+    }
 }
 
-pub async fn forbidden(
-) -> Result<Option<String>, String> {
-    Err("Letter submissions are disabled".to_string())
+pub async fn forbidden<T>() -> Result<T, CommandError> {
+    metrics::LETTERS_REJECTED
+        .with_label_values(&["disabled"])
+        .inc();
+    Err("Letter submissions are disabled".into())
 }
 
 pub async fn complete(
     interaction: &AutocompleteInteraction,
     ctx: &Context,
-    db_conn: &mut SqliteConnection,
-) -> Result<(), &'static str> {
-    use crate::schema::recipients::dsl::{fullname, recipients};
-
+    db: &Repository,
+) -> Result<(), CommandError> {
     let up_to_now = as_string(
         interaction
             .data
             .options
-            .get(0)
-            .ok_or("No recipient found")?
+            .first()
+            .ok_or(CommandError::Malformed("No recipient found"))?
             .resolved
             .as_ref()
-            .ok_or("Expected recipient object")?,
+            .ok_or(CommandError::Malformed("Expected recipient object"))?,
     )
-    .map_err(|_| "Recipient is not string")?;
+    .map_err(|_| CommandError::Malformed("Recipient is not string"))?;
+
+    let names = db.search_recipients(up_to_now);
 
     interaction
         .create_autocomplete_response(ctx, |response| {
-            let names: Vec<String> = recipients
-                .filter(fullname.like(format!("%{up_to_now}%")))
-                .select(fullname)
-                .limit(25)
-                .load(db_conn)
-                .unwrap();
-
             for name in names {
                 response.add_string_choice(&name, &name);
             }
 
             response
         })
-        .await
-        .unwrap();
+        .await?;
     Ok(())
 }
 
@@ -192,11 +306,22 @@ pub struct ValentineLetter {
     pub recipient: String,
     pub letter: String,
     pub anon: bool,
+    /// When the letter was sent, in UTC.
+    pub sent_at: NaiveDateTime,
+    /// The guild the letter was sent from, if it was not sent in DMs.
+    pub guild_id: Option<String>,
+    pub channel_id: Option<String>,
 }
 
 #[derive(Debug)]
 pub struct ParseOptionsError(&'static str);
 
+impl std::fmt::Display for ParseOptionsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
 impl TryFrom<&ApplicationCommandInteraction> for ValentineLetter {
     type Error = ParseOptionsError;
 
@@ -206,7 +331,7 @@ impl TryFrom<&ApplicationCommandInteraction> for ValentineLetter {
 
         let recipient = as_string(
             options
-                .get(0)
+                .first()
                 .ok_or(ParseOptionsError("No recipient found"))?
                 .resolved
                 .as_ref()
@@ -236,14 +361,47 @@ impl TryFrom<&ApplicationCommandInteraction> for ValentineLetter {
         )
         .map_err(|_| ParseOptionsError("Anonymous is not boolean"))?;
 
-        Ok(ValentineLetter {
+        let letter = ValentineLetter {
             sender: user.name.clone(),
             recipient: recipient.to_string(),
             letter: letter.to_string(),
             anon: *is_anon,
             sender_id: value.user.id.to_string(),
-        })
+            sent_at: Utc::now().naive_utc(),
+            guild_id: value.guild_id.map(|id| id.to_string()),
+            channel_id: Some(value.channel_id.to_string()),
+        };
+        letter.validate()?;
+
+        Ok(letter)
     }
 }
 
-struct DatabaseProblem;
+impl ValentineLetter {
+    /// Checks the rules Discord enforces on the `/sendletter` options, so
+    /// letters that did not come through Discord are held to them too.
+    pub fn validate(&self) -> Result<(), ParseOptionsError> {
+        if self.sender.is_empty() {
+            return Err(ParseOptionsError("No sender found"));
+        }
+
+        if self.sender_id.is_empty() {
+            return Err(ParseOptionsError("No sender ID found"));
+        }
+
+        let recipient_length = self.recipient.chars().count();
+        if recipient_length == 0 || recipient_length > usize::from(RECIPIENT_MAX_LENGTH) {
+            return Err(ParseOptionsError(
+                "Recipient must be between 1 and 20 characters long",
+            ));
+        }
+
+        if self.letter.chars().count() < usize::from(LETTER_MIN_LENGTH) {
+            return Err(ParseOptionsError(
+                "Letter must be at least 100 characters long",
+            ));
+        }
+
+        Ok(())
+    }
+}
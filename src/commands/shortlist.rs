@@ -0,0 +1,105 @@
+use serenity::{
+    async_trait,
+    builder::CreateApplicationCommand,
+    model::prelude::{
+        command::CommandOptionType,
+        interaction::{
+            application_command::{ApplicationCommandInteraction, CommandDataOptionValue},
+            message_component::MessageComponentInteraction,
+        },
+    },
+    prelude::Context,
+};
+
+use super::{find_option, CommandError, SlashCommand};
+use crate::permissions::ModAction;
+use crate::repository::Repository;
+use crate::votes::shortlist;
+
+const DEFAULT_LENGTH: i64 = 10;
+
+pub const NAME: &str = "shortlist";
+
+pub struct ShortlistCommand;
+
+#[async_trait]
+impl SlashCommand for ShortlistCommand {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn action(&self) -> Option<ModAction> {
+        Some(ModAction::Review)
+    }
+
+    fn register<'a>(
+        &self,
+        command: &'a mut CreateApplicationCommand,
+    ) -> &'a mut CreateApplicationCommand {
+        command
+            .name(NAME)
+            .description("ranks letters by the votes of moderators")
+            .create_option(|option| {
+                option
+                    .name("length")
+                    .description("how many letters to show")
+                    .kind(CommandOptionType::Integer)
+                    .min_int_value(1)
+                    .max_int_value(25)
+                    .required(false)
+            })
+            .dm_permission(false)
+    }
+
+    async fn run(
+        &self,
+        command: &ApplicationCommandInteraction,
+        _ctx: &Context,
+        db: &Repository,
+    ) -> Result<Option<String>, CommandError> {
+        let length = match find_option(&command.data.options, "length") {
+            Some(CommandDataOptionValue::Integer(length)) => *length,
+            _ => DEFAULT_LENGTH,
+        };
+
+        let ranked = db.query(shortlist).await?;
+
+        if ranked.is_empty() {
+            return Ok(Some("There are no letters yet.".to_owned()));
+        }
+
+        Ok(Some(
+            ranked
+                .iter()
+                .take(usize::try_from(length).unwrap_or(0))
+                .zip(1..)
+                .map(|((letter, score), rank)| {
+                    format!(
+                        "{rank}. Letter #{} to {}: {score}",
+                        letter.id, letter.recipient
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+        ))
+    }
+
+    /// The voting buttons on audit messages, which decide the shortlist.
+    fn custom_ids(&self) -> &'static [&'static str] {
+        &["vote_up", "vote_down"]
+    }
+
+    async fn component(
+        &self,
+        interaction: &MessageComponentInteraction,
+        ctx: &Context,
+        db: &Repository,
+    ) -> Result<(), CommandError> {
+        let vote = if interaction.data.custom_id == "vote_up" {
+            1
+        } else {
+            -1
+        };
+        super::vote::handle_button(interaction, ctx, db, vote).await
+    }
+}
@@ -0,0 +1,128 @@
+use serenity::{
+    async_trait,
+    builder::{CreateApplicationCommand, CreateApplicationCommands},
+    model::prelude::interaction::{
+        application_command::ApplicationCommandInteraction, autocomplete::AutocompleteInteraction,
+        message_component::MessageComponentInteraction, modal::ModalSubmitInteraction,
+    },
+    prelude::Context,
+};
+
+use super::CommandError;
+use crate::permissions::{Access, ModAction};
+use crate::repository::Repository;
+
+/// A slash command, along with the buttons, modals and autocompletion it
+/// owns. Add new commands to [`super::registry`].
+#[async_trait]
+pub trait SlashCommand: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn register<'a>(
+        &self,
+        command: &'a mut CreateApplicationCommand,
+    ) -> &'a mut CreateApplicationCommand;
+
+    /// What members have to be allowed to do to use the command, or `None`
+    /// if anyone may use it.
+    fn action(&self) -> Option<ModAction>;
+
+    /// What members have to be allowed to do to use the button or modal with
+    /// this custom ID. The same as for the command unless overridden.
+    fn component_action(&self, _custom_id: &str) -> Option<ModAction> {
+        self.action()
+    }
+
+    /// Runs the command. Returning a message answers the interaction with it.
+    async fn run(
+        &self,
+        command: &ApplicationCommandInteraction,
+        ctx: &Context,
+        db: &Repository,
+    ) -> Result<Option<String>, CommandError>;
+
+    /// Custom IDs of the buttons and modals handled by this command.
+    fn custom_ids(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    async fn component(
+        &self,
+        _interaction: &MessageComponentInteraction,
+        _ctx: &Context,
+        _db: &Repository,
+    ) -> Result<(), CommandError> {
+        Err(CommandError::Malformed("command has no buttons"))
+    }
+
+    async fn modal(
+        &self,
+        _interaction: &mut ModalSubmitInteraction,
+        _ctx: &Context,
+        _db: &Repository,
+    ) -> Result<(), CommandError> {
+        Err(CommandError::Malformed("command has no modals"))
+    }
+
+    async fn autocomplete(
+        &self,
+        _interaction: &AutocompleteInteraction,
+        _ctx: &Context,
+        _db: &Repository,
+    ) -> Result<(), CommandError> {
+        Ok(())
+    }
+}
+
+/// Every command the bot has, used both to register them with Discord and to
+/// route interactions to them.
+pub struct Registry {
+    commands: Vec<Box<dyn SlashCommand>>,
+}
+
+impl Registry {
+    pub fn new(commands: Vec<Box<dyn SlashCommand>>) -> Self {
+        Self { commands }
+    }
+
+    /// Adds every command, letting Discord show moderation commands to the
+    /// members `access` allows to use them.
+    pub fn register_all<'a>(
+        &self,
+        commands: &'a mut CreateApplicationCommands,
+        access: &Access,
+    ) -> &'a mut CreateApplicationCommands {
+        for command in &self.commands {
+            commands.create_application_command(|builder| {
+                command.register(builder);
+                match command
+                    .action()
+                    .map(|action| access.default_member_permissions(action))
+                {
+                    Some(Some(permissions)) => builder.default_member_permissions(permissions),
+                    Some(None) => {
+                        builder.0.remove("default_member_permissions");
+                        builder
+                    }
+                    None => builder,
+                }
+            });
+        }
+        commands
+    }
+
+    pub fn command(&self, name: &str) -> Option<&dyn SlashCommand> {
+        self.commands
+            .iter()
+            .find(|command| command.name() == name)
+            .map(|command| command.as_ref())
+    }
+
+    /// The command that owns the button or modal with this custom ID.
+    pub fn owner_of(&self, custom_id: &str) -> Option<&dyn SlashCommand> {
+        self.commands
+            .iter()
+            .find(|command| command.custom_ids().contains(&custom_id))
+            .map(|command| command.as_ref())
+    }
+}
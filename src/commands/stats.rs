@@ -0,0 +1,284 @@
+use std::sync::{Arc, Mutex};
+
+use serenity::{
+    async_trait,
+    builder::{CreateApplicationCommand, CreateEmbed},
+    http::Http,
+    model::{
+        prelude::{
+            command::CommandOptionType,
+            interaction::{
+                application_command::ApplicationCommandInteraction, InteractionResponseType,
+            },
+            AttachmentType, ChannelId, MessageId,
+        },
+        Timestamp,
+    },
+    prelude::Context,
+};
+use tokio::time::{interval, Duration};
+use tracing::error;
+
+use super::{as_boolean, find_option, CommandError, SlashCommand};
+use crate::chart::{bar_chart, timeline};
+use crate::permissions::ModAction;
+use crate::repository::{Repository, RepositoryError};
+use crate::stats::{self, collect, Stats};
+
+/// The pinned statistics message that is kept up to date, if there is one.
+pub type Dashboard = Arc<Mutex<Option<(ChannelId, MessageId)>>>;
+
+const DASHBOARD_REFRESH: Duration = Duration::from_secs(60);
+
+/// Embed fields can hold at most 1024 characters.
+const MAX_FIELD_LENGTH: usize = 1000;
+
+fn list_field(lines: impl Iterator<Item = String>) -> String {
+    let mut field = String::new();
+    for line in lines {
+        if field.len() + line.len() + 1 > MAX_FIELD_LENGTH {
+            field.push_str("\n...");
+            break;
+        }
+        if !field.is_empty() {
+            field.push('\n');
+        }
+        field.push_str(&line);
+    }
+
+    if field.is_empty() {
+        "None yet".to_owned()
+    } else {
+        field
+    }
+}
+
+pub fn build_embed<'a>(stats: &Stats, e: &'a mut CreateEmbed) -> &'a mut CreateEmbed {
+    e.title("Valentine's letters so far")
+        .field("Letters", stats.letters, true)
+        .field(
+            "Anonymous",
+            format!("{} ({:.0}%)", stats.anonymous, stats.anonymous_percentage()),
+            true,
+        )
+        .field("Unique senders", stats.unique_senders, true)
+        .field(
+            "Recipients",
+            format!(
+                "{} to real people, {} to fictional characters, {} to unlisted names",
+                stats.to_real, stats.to_fictional, stats.to_unlisted
+            ),
+            false,
+        )
+        .field(
+            "Letters per recipient",
+            list_field(
+                stats
+                    .per_recipient
+                    .iter()
+                    .map(|(recipient, count)| format!("{recipient}: {count}")),
+            ),
+            false,
+        )
+        .field(
+            "Submissions per day",
+            list_field(
+                stats
+                    .per_day
+                    .iter()
+                    .map(|(day, count)| format!("{day}: {count}")),
+            ),
+            false,
+        )
+        .footer(|f| f.text("2023 Classroom of the Elite Valentine's Event"))
+        .timestamp(Timestamp::now())
+        .colour((255, 105, 180))
+}
+
+pub const NAME: &str = "stats";
+
+pub struct StatsCommand {
+    pub dashboard: Dashboard,
+    /// Where the dashboard is pinned, instead of where `/stats` was used.
+    pub audit_channel: Option<ChannelId>,
+}
+
+#[async_trait]
+impl SlashCommand for StatsCommand {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn action(&self) -> Option<ModAction> {
+        Some(ModAction::Review)
+    }
+
+    fn register<'a>(
+        &self,
+        command: &'a mut CreateApplicationCommand,
+    ) -> &'a mut CreateApplicationCommand {
+        command
+            .name(NAME)
+            .description("shows statistics about the letters sent so far")
+            .create_option(|subcommand| {
+                subcommand
+                    .name("summary")
+                    .description("shows the numbers")
+                    .kind(CommandOptionType::SubCommand)
+                    .create_sub_option(|option| {
+                        option
+                            .name("pin")
+                            .description(
+                                "whether to pin the statistics in the mod channel and keep them updated",
+                            )
+                            .kind(CommandOptionType::Boolean)
+                            .required(false)
+                    })
+            })
+            .create_option(|subcommand| {
+                subcommand
+                    .name("chart")
+                    .description("shows charts of letters per recipient and per day")
+                    .kind(CommandOptionType::SubCommand)
+            })
+            .dm_permission(false)
+    }
+
+    async fn run(
+        &self,
+        command: &ApplicationCommandInteraction,
+        ctx: &Context,
+        db: &Repository,
+    ) -> Result<Option<String>, CommandError> {
+        let subcommand = command.data.options.first().ok_or("No subcommand found")?;
+
+        let stats = db.query(collect).await?;
+
+        if subcommand.name == "chart" {
+            return charts(command, ctx, &stats).await;
+        }
+
+        let pin = find_option(&subcommand.options, "pin")
+            .and_then(|val| as_boolean(val).ok())
+            .copied()
+            .unwrap_or(false);
+
+        if !pin {
+            command
+                .create_interaction_response(ctx, |response| {
+                    response
+                        .kind(InteractionResponseType::ChannelMessageWithSource)
+                        .interaction_response_data(|message| {
+                            message.embed(|e| build_embed(&stats, e)).ephemeral(true)
+                        })
+                })
+                .await?;
+
+            return Ok(None);
+        }
+
+        let channel_id = self.audit_channel.unwrap_or(command.channel_id);
+        let message = channel_id
+            .send_message(ctx, |m| m.embed(|e| build_embed(&stats, e)))
+            .await?;
+        message.pin(ctx).await?;
+        let (channel, pinned) = (channel_id.to_string(), message.id.to_string());
+        db.query(move |conn| stats::save_dashboard(conn, &channel, &pinned))
+            .await?;
+
+        let previous = self
+            .dashboard
+            .lock()
+            .unwrap()
+            .replace((channel_id, message.id));
+        if let Some((channel_id, message_id)) = previous {
+            let _ = channel_id.unpin(ctx, message_id).await;
+        }
+
+        Ok(Some(format!(
+            "Pinned the statistics in <#{channel_id}>, they will be updated every minute."
+        )))
+    }
+}
+
+async fn charts(
+    command: &ApplicationCommandInteraction,
+    ctx: &Context,
+    stats: &Stats,
+) -> Result<Option<String>, CommandError> {
+    // charts with many recipients take a while to draw
+    command
+        .create_interaction_response(ctx, |response| {
+            response
+                .kind(InteractionResponseType::DeferredChannelMessageWithSource)
+                .interaction_response_data(|message| message.ephemeral(true))
+        })
+        .await?;
+    let (per_recipient, per_day) = (stats.per_recipient.clone(), stats.per_day.clone());
+    let (per_recipient, per_day) = tokio::task::spawn_blocking(move || {
+        Ok::<_, String>((
+            bar_chart("Letters per recipient", &per_recipient)?,
+            timeline("Submissions per day", &per_day)?,
+        ))
+    })
+    .await
+    .map_err(|e| format!("Could not draw charts: {e}"))??;
+
+    command
+        .create_followup_message(ctx, |message| {
+            message
+                .add_file(AttachmentType::Bytes {
+                    data: per_recipient.into(),
+                    filename: "letters_per_recipient.png".to_owned(),
+                })
+                .add_file(AttachmentType::Bytes {
+                    data: per_day.into(),
+                    filename: "submissions_per_day.png".to_owned(),
+                })
+                .ephemeral(true)
+        })
+        .await?;
+
+    Ok(None)
+}
+
+/// The dashboard pinned before the bot last stopped, if there is one.
+pub async fn load_dashboard(db: &Repository) -> Result<Dashboard, RepositoryError> {
+    let pinned = db
+        .query(stats::load_dashboard)
+        .await?
+        .and_then(|(channel, message)| {
+            Some((
+                ChannelId(channel.parse().ok()?),
+                MessageId(message.parse().ok()?),
+            ))
+        });
+    Ok(Arc::new(Mutex::new(pinned)))
+}
+
+/// Refreshes the pinned statistics, if there are any, until the bot stops.
+pub async fn keep_dashboard_updated(http: Arc<Http>, db: Repository, dashboard: Dashboard) {
+    let mut timer = interval(DASHBOARD_REFRESH);
+    loop {
+        timer.tick().await;
+
+        let Some((channel_id, message_id)) = *dashboard.lock().unwrap() else {
+            continue;
+        };
+
+        let stats = match db.query(collect).await {
+            Ok(stats) => stats,
+            Err(why) => {
+                error!("Cannot collect statistics: {why}");
+                continue;
+            }
+        };
+
+        if let Err(why) = channel_id
+            .edit_message(&http, message_id, |m| m.embed(|e| build_embed(&stats, e)))
+            .await
+        {
+            error!("Cannot update statistics message: {why}");
+        }
+    }
+}
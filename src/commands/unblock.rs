@@ -0,0 +1,72 @@
+use serenity::{
+    async_trait,
+    builder::CreateApplicationCommand,
+    model::prelude::{
+        command::CommandOptionType, interaction::application_command::ApplicationCommandInteraction,
+    },
+    prelude::Context,
+};
+use tracing::info;
+
+use super::{as_user, find_option, CommandError, SlashCommand};
+use crate::audit::{self, Action};
+use crate::blocklist;
+use crate::permissions::ModAction;
+use crate::repository::{LetterRepository, Repository};
+
+pub const NAME: &str = "unblock";
+
+pub struct UnblockCommand;
+
+#[async_trait]
+impl SlashCommand for UnblockCommand {
+    fn name(&self) -> &'static str {
+        NAME
+    }
+
+    fn action(&self) -> Option<ModAction> {
+        Some(ModAction::Block)
+    }
+
+    fn register<'a>(
+        &self,
+        command: &'a mut CreateApplicationCommand,
+    ) -> &'a mut CreateApplicationCommand {
+        command
+            .name(NAME)
+            .description("Lets a blocked user send letters again")
+            .create_option(|option| {
+                option
+                    .name("user")
+                    .description("Who to unblock")
+                    .kind(CommandOptionType::User)
+                    .required(true)
+            })
+            .dm_permission(false)
+    }
+
+    async fn run(
+        &self,
+        command: &ApplicationCommandInteraction,
+        _ctx: &Context,
+        db: &Repository,
+    ) -> Result<Option<String>, CommandError> {
+        let user = find_option(&command.data.options, "user")
+            .and_then(|user| as_user(user).ok())
+            .ok_or(CommandError::Malformed("user is not a user"))?
+            .clone();
+
+        let user_id = user.id.to_string();
+        if !db
+            .query(move |conn| blocklist::unblock(conn, &user_id))
+            .await?
+        {
+            return Err(format!("{} is not blocked", user.tag()).into());
+        }
+        db.record_event(Action::Unblock.by(command.user.id).on(audit::user(user.id)))
+            .await?;
+        info!("Unblocked a user");
+
+        Ok(Some(format!("{} can send letters again", user.tag())))
+    }
+}
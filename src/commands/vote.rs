@@ -0,0 +1,41 @@
+use serenity::{
+    model::prelude::interaction::{
+        message_component::MessageComponentInteraction, InteractionResponseType,
+    },
+    prelude::Context,
+};
+
+use super::CommandError;
+use crate::audit::{self, Action};
+use crate::logging;
+use crate::repository::{LetterRepository, Repository};
+use crate::votes::cast_vote;
+
+pub async fn handle_button(
+    interaction: &MessageComponentInteraction,
+    ctx: &Context,
+    db: &Repository,
+    vote: i32,
+) -> Result<(), CommandError> {
+    let audit_message = interaction.message.id.to_string();
+    let moderator = interaction.user.id.to_string();
+    let (letter, score) = db
+        .query(move |conn| cast_vote(conn, &audit_message, &moderator, vote))
+        .await?;
+    logging::record_letter(letter.id);
+    let voted = Action::Vote
+        .by(interaction.user.id)
+        .on(audit::letter(letter.id))
+        .details(format!("{vote:+}"));
+    db.record_event(voted).await?;
+    let content = format!("Letter #{} now has a score of {score}", letter.id);
+
+    interaction
+        .create_interaction_response(ctx, |response| {
+            response
+                .kind(InteractionResponseType::ChannelMessageWithSource)
+                .interaction_response_data(|data| data.content(content).ephemeral(true))
+        })
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,69 @@
+use std::collections::HashMap;
+use std::env;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+/// How long senders wait between letters unless `LETTER_COOLDOWN` says
+/// otherwise.
+pub const DEFAULT_COOLDOWN: Duration = Duration::from_secs(60);
+
+/// Makes senders wait between letters, so retrying over and over does not
+/// flood the audit channel. Only kept in memory, as restarting the bot takes
+/// longer than the wait.
+pub struct Cooldown {
+    period: Duration,
+    /// When each sender, by ID, last sent a letter.
+    last_sent: Mutex<HashMap<String, Instant>>,
+}
+
+impl Default for Cooldown {
+    fn default() -> Self {
+        Cooldown::new(DEFAULT_COOLDOWN)
+    }
+}
+
+impl Cooldown {
+    /// A cooldown of `period`. A period of zero lets senders send right away.
+    pub fn new(period: Duration) -> Self {
+        Self {
+            period,
+            last_sent: Mutex::default(),
+        }
+    }
+
+    /// Reads the cooldown in seconds from `LETTER_COOLDOWN`.
+    pub fn from_env() -> Result<Self, String> {
+        match env::var("LETTER_COOLDOWN") {
+            Err(_) => Ok(Cooldown::new(DEFAULT_COOLDOWN)),
+            Ok(seconds) => seconds
+                .parse()
+                .map(|seconds| Cooldown::new(Duration::from_secs(seconds)))
+                .map_err(|e| format!("LETTER_COOLDOWN must be a number of seconds: {e}")),
+        }
+    }
+
+    /// Starts the cooldown for `sender_id`, or returns how much longer they
+    /// have to wait if it is still running.
+    pub fn start(&self, sender_id: &str) -> Result<(), Duration> {
+        if self.period.is_zero() {
+            return Ok(());
+        }
+
+        let now = Instant::now();
+        let mut last_sent = self.last_sent.lock().unwrap();
+        last_sent.retain(|_, sent| now.duration_since(*sent) < self.period);
+        if let Some(sent) = last_sent.get(sender_id) {
+            return Err(self.period - now.duration_since(*sent));
+        }
+        last_sent.insert(sender_id.to_owned(), now);
+        Ok(())
+    }
+
+    /// Forgets the cooldown `sender_id` started last, as their letter was not
+    /// sent after all.
+    pub fn cancel(&self, sender_id: &str) {
+        self.last_sent.lock().unwrap().remove(sender_id);
+    }
+}
@@ -0,0 +1,152 @@
+use std::sync::Arc;
+
+use serenity::{
+    async_trait,
+    builder::{CreateInteractionResponse, CreateMessage, EditInteractionResponse, EditMessage},
+    http::{Http, HttpError, StatusCode},
+    json::{hashmap_to_json_map, Value},
+    model::guild::Member,
+    model::id::{ChannelId, GuildId, InteractionId, MessageId, UserId},
+};
+
+/// The requests to Discord made by the letter flows, so they can be tested
+/// without a live bot. [`Http`] makes the actual requests.
+#[async_trait]
+pub trait Discord: Send + Sync {
+    /// Answers an interaction.
+    async fn respond<'a>(
+        &self,
+        interaction: InteractionId,
+        token: &str,
+        response: CreateInteractionResponse<'a>,
+    ) -> serenity::Result<()>;
+
+    /// Edits the answer to an interaction, such as a deferred one.
+    async fn edit_response(
+        &self,
+        token: &str,
+        edit: EditInteractionResponse,
+    ) -> serenity::Result<()>;
+
+    async fn send_message<'a>(
+        &self,
+        channel: ChannelId,
+        message: CreateMessage<'a>,
+    ) -> serenity::Result<MessageId>;
+
+    async fn edit_message<'a>(
+        &self,
+        channel: ChannelId,
+        message: MessageId,
+        edit: EditMessage<'a>,
+    ) -> serenity::Result<()>;
+
+    /// Looks up a member of a guild, or `None` if the user is not in it.
+    async fn member(&self, guild: GuildId, user: UserId) -> serenity::Result<Option<Member>>;
+
+    /// Shows the bot as typing in a channel until [`Typing::stop`] is called.
+    #[allow(clippy::result_large_err)] // serenity's own error type
+    fn start_typing(&self, channel: ChannelId) -> serenity::Result<Typing>;
+}
+
+/// A typing indicator started by [`Discord::start_typing`].
+pub struct Typing(Option<serenity::http::Typing>);
+
+impl Typing {
+    /// A typing indicator that is not shown anywhere.
+    pub fn none() -> Self {
+        Typing(None)
+    }
+
+    pub fn stop(self) {
+        if let Some(typing) = self.0 {
+            let _ = typing.stop();
+        }
+    }
+}
+
+/// Fills in a default builder, the way serenity's own methods do, for passing
+/// it to [`Discord`].
+pub fn build<T: Default>(f: impl FnOnce(&mut T) -> &mut T) -> T {
+    let mut builder = T::default();
+    f(&mut builder);
+    builder
+}
+
+#[async_trait]
+impl Discord for Arc<Http> {
+    async fn respond<'a>(
+        &self,
+        interaction: InteractionId,
+        token: &str,
+        response: CreateInteractionResponse<'a>,
+    ) -> serenity::Result<()> {
+        let map = Value::from(hashmap_to_json_map(response.0));
+        if response.1.is_empty() {
+            self.create_interaction_response(interaction.0, token, &map)
+                .await
+        } else {
+            self.create_interaction_response_with_files(interaction.0, token, &map, response.1)
+                .await
+        }
+    }
+
+    async fn edit_response(
+        &self,
+        token: &str,
+        edit: EditInteractionResponse,
+    ) -> serenity::Result<()> {
+        let map = Value::from(hashmap_to_json_map(edit.0));
+        self.edit_original_interaction_response(token, &map)
+            .await
+            .map(|_| ())
+    }
+
+    async fn send_message<'a>(
+        &self,
+        channel: ChannelId,
+        message: CreateMessage<'a>,
+    ) -> serenity::Result<MessageId> {
+        channel
+            .send_message(self, |m| {
+                *m = message;
+                m
+            })
+            .await
+            .map(|sent| sent.id)
+    }
+
+    async fn edit_message<'a>(
+        &self,
+        channel: ChannelId,
+        message: MessageId,
+        edit: EditMessage<'a>,
+    ) -> serenity::Result<()> {
+        channel
+            .edit_message(self, message, |m| {
+                *m = edit;
+                m
+            })
+            .await
+            .map(|_| ())
+    }
+
+    async fn member(&self, guild: GuildId, user: UserId) -> serenity::Result<Option<Member>> {
+        match self.get_member(guild.0, user.0).await {
+            Ok(member) => Ok(Some(member)),
+            Err(serenity::Error::Http(e))
+                if matches!(&*e, HttpError::UnsuccessfulRequest(response)
+                    if response.status_code == StatusCode::NOT_FOUND) =>
+            {
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    fn start_typing(&self, channel: ChannelId) -> serenity::Result<Typing> {
+        channel
+            .start_typing(self)
+            .map(|typing| Typing(Some(typing)))
+    }
+}
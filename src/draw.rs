@@ -0,0 +1,187 @@
+use ab_glyph::{point, Font, FontRef, GlyphId, PxScale, ScaleFont};
+use tiny_skia::{Color, Mask, Paint, Path, PathBuilder, Pixmap, PixmapPaint, Rect, Transform};
+
+// Fonts are bundled so images look the same wherever the bot runs.
+static REGULAR: &[u8] = include_bytes!("../assets/fonts/DejaVuSerif.ttf");
+static BOLD: &[u8] = include_bytes!("../assets/fonts/DejaVuSerif-Bold.ttf");
+
+pub fn regular() -> FontRef<'static> {
+    FontRef::try_from_slice(REGULAR).expect("bundled font is valid")
+}
+
+pub fn bold() -> FontRef<'static> {
+    FontRef::try_from_slice(BOLD).expect("bundled font is valid")
+}
+
+pub fn rgb(hex: u32) -> Color {
+    Color::from_rgba8((hex >> 16) as u8, (hex >> 8) as u8, hex as u8, 255)
+}
+
+pub fn paint(color: Color) -> Paint<'static> {
+    let mut paint = Paint::default();
+    paint.set_color(color);
+    paint.anti_alias = true;
+    paint
+}
+
+/// Positions of every glyph in `text` on a single line, and the x coordinate
+/// the line ends at.
+fn layout(font: &FontRef, size: f32, text: &str, x: f32) -> (Vec<(GlyphId, f32)>, f32) {
+    let scaled = font.as_scaled(PxScale::from(size));
+    let mut caret = x;
+    let mut previous = None;
+    let mut glyphs = vec![];
+
+    for c in text.chars() {
+        let id = scaled.glyph_id(c);
+        if let Some(previous) = previous {
+            caret += scaled.kern(previous, id);
+        }
+        glyphs.push((id, caret));
+        caret += scaled.h_advance(id);
+        previous = Some(id);
+    }
+
+    (glyphs, caret)
+}
+
+pub fn text_width(font: &FontRef, size: f32, text: &str) -> f32 {
+    layout(font, size, text, 0.0).1
+}
+
+/// Draws a single line of text with its baseline at `y`.
+pub fn draw_text(
+    pixmap: &mut Pixmap,
+    font: &FontRef,
+    size: f32,
+    text: &str,
+    x: f32,
+    y: f32,
+    color: Color,
+) {
+    let glyphs: Vec<_> = layout(font, size, text, x)
+        .0
+        .into_iter()
+        .filter_map(|(id, glyph_x)| {
+            font.outline_glyph(id.with_scale_and_position(size, point(glyph_x, y)))
+        })
+        .collect();
+
+    // The text is drawn on an image only as large as the text itself, as
+    // masking the whole image for every line is slow on long cards.
+    let Some((left, top, right, bottom)) = glyphs
+        .iter()
+        .map(|glyph| glyph.px_bounds())
+        .map(|bounds| (bounds.min.x, bounds.min.y, bounds.max.x, bounds.max.y))
+        .reduce(|a, b| (a.0.min(b.0), a.1.min(b.1), a.2.max(b.2), a.3.max(b.3)))
+    else {
+        return;
+    };
+    let width = (right - left).ceil() as u32;
+    let height = (bottom - top).ceil() as u32;
+    let (Some(mut mask), Some(mut text)) = (Mask::new(width, height), Pixmap::new(width, height))
+    else {
+        return;
+    };
+    let coverage = mask.data_mut();
+
+    for glyph in &glyphs {
+        let bounds = glyph.px_bounds();
+        let offset_x = (bounds.min.x - left) as usize;
+        let offset_y = (bounds.min.y - top) as usize;
+        glyph.draw(|gx, gy, value| {
+            let px = offset_x + gx as usize;
+            let py = offset_y + gy as usize;
+            if px < width as usize && py < height as usize {
+                let pixel = &mut coverage[py * width as usize + px];
+                *pixel = (*pixel).max((value.clamp(0.0, 1.0) * 255.0) as u8);
+            }
+        });
+    }
+
+    if let Some(rect) = Rect::from_xywh(0.0, 0.0, width as f32, height as f32) {
+        text.fill_rect(rect, &paint(color), Transform::identity(), Some(&mask));
+    }
+    pixmap.draw_pixmap(
+        left as i32,
+        top as i32,
+        text.as_ref(),
+        &PixmapPaint::default(),
+        Transform::identity(),
+        None,
+    );
+}
+
+/// Breaks `text` into lines no wider than `max_width`, keeping the line breaks
+/// already in the text. Words that are too long by themselves are split.
+pub fn wrap(font: &FontRef, size: f32, text: &str, max_width: f32) -> Vec<String> {
+    let mut lines = vec![];
+
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            let candidate = if line.is_empty() {
+                word.to_owned()
+            } else {
+                format!("{line} {word}")
+            };
+
+            if text_width(font, size, &candidate) <= max_width {
+                line = candidate;
+                continue;
+            }
+
+            if !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+            for c in word.chars() {
+                line.push(c);
+                if text_width(font, size, &line) > max_width && line.chars().count() > 1 {
+                    line.pop();
+                    lines.push(std::mem::replace(&mut line, c.to_string()));
+                }
+            }
+        }
+        lines.push(line);
+    }
+
+    lines
+}
+
+pub fn rounded_rect(x: f32, y: f32, width: f32, height: f32, radius: f32) -> Option<Path> {
+    let radius = radius.min(width / 2.0).min(height / 2.0);
+    let mut pb = PathBuilder::new();
+    pb.move_to(x + radius, y);
+    pb.line_to(x + width - radius, y);
+    pb.quad_to(x + width, y, x + width, y + radius);
+    pb.line_to(x + width, y + height - radius);
+    pb.quad_to(x + width, y + height, x + width - radius, y + height);
+    pb.line_to(x + radius, y + height);
+    pb.quad_to(x, y + height, x, y + height - radius);
+    pb.line_to(x, y + radius);
+    pb.quad_to(x, y, x + radius, y);
+    pb.close();
+    pb.finish()
+}
+
+/// A heart of roughly `size` pixels across, centred on `(x, y)`.
+pub fn heart(x: f32, y: f32, size: f32) -> Option<Path> {
+    let p = |dx: f32, dy: f32| (x + dx * size, y + dy * size);
+    let mut pb = PathBuilder::new();
+
+    let (sx, sy) = p(0.0, 0.45);
+    pb.move_to(sx, sy);
+    for [c1, c2, end] in [
+        [(-0.55, 0.05), (-0.65, -0.45), (-0.3, -0.5)],
+        [(-0.12, -0.53), (0.0, -0.4), (0.0, -0.28)],
+        [(0.0, -0.4), (0.12, -0.53), (0.3, -0.5)],
+        [(0.65, -0.45), (0.55, 0.05), (0.0, 0.45)],
+    ] {
+        let (x1, y1) = p(c1.0, c1.1);
+        let (x2, y2) = p(c2.0, c2.1);
+        let (x3, y3) = p(end.0, end.1);
+        pb.cubic_to(x1, y1, x2, y2, x3, y3);
+    }
+    pb.close();
+    pb.finish()
+}
@@ -0,0 +1,106 @@
+use std::collections::HashSet;
+
+use diesel::prelude::*;
+
+use crate::encryption::TryEncrypted;
+use crate::model::NO_SENDER_ID;
+
+/// How alike two letters have to be to count as the same letter, from 0 (no
+/// phrase in common) to 1 (the same words in the same order).
+pub const SIMILARITY_THRESHOLD: f64 = 0.7;
+
+/// How many words in a row make up a phrase when comparing letters.
+const PHRASE_LENGTH: usize = 2;
+
+/// An earlier letter that a new one looks a lot like.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Duplicate {
+    pub letter_id: i32,
+    /// Whether the earlier letter came from the same sender.
+    pub own: bool,
+    pub similarity: f64,
+}
+
+impl std::fmt::Display for Duplicate {
+    /// Why a letter was flagged, for moderators.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:.0}% like {} letter #{}",
+            self.similarity * 100.0,
+            if self.own {
+                "their"
+            } else {
+                "another sender's"
+            },
+            self.letter_id
+        )
+    }
+}
+
+/// The words of a letter, ignoring case and punctuation.
+fn words(letter: &str) -> Vec<String> {
+    letter
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Every run of [`PHRASE_LENGTH`] words in a letter, or the whole letter if
+/// it is shorter than that.
+fn phrases(letter: &str) -> HashSet<Vec<String>> {
+    let words = words(letter);
+    if words.len() < PHRASE_LENGTH {
+        return HashSet::from([words]);
+    }
+    words
+        .windows(PHRASE_LENGTH)
+        .map(|phrase| phrase.to_vec())
+        .collect()
+}
+
+/// The share of phrases two letters have in common. Changing a few words,
+/// the punctuation or the capitalization keeps letters alike.
+pub fn similarity(a: &str, b: &str) -> f64 {
+    jaccard(&phrases(a), &phrases(b))
+}
+
+/// The stored letter most like `content`, if any is alike enough. Letters
+/// from the same sender are preferred, as sending one twice is the more
+/// likely mistake.
+pub fn find_duplicate(
+    conn: &mut SqliteConnection,
+    sender: &str,
+    content: &str,
+) -> QueryResult<Option<Duplicate>> {
+    use crate::schema::letters::dsl::{content as letter_content, id, letters, sender_id};
+
+    let stored: Vec<(i32, TryEncrypted, TryEncrypted)> =
+        letters.select((id, sender_id, letter_content)).load(conn)?;
+
+    let phrases_of_new = phrases(content);
+    Ok(stored
+        .into_iter()
+        .map(
+            |(letter_id, TryEncrypted(stored_sender), TryEncrypted(stored_content))| Duplicate {
+                letter_id,
+                own: stored_sender == sender && stored_sender != NO_SENDER_ID,
+                similarity: jaccard(&phrases_of_new, &phrases(&stored_content)),
+            },
+        )
+        .filter(|duplicate| duplicate.similarity >= SIMILARITY_THRESHOLD)
+        .max_by(|a, b| {
+            (a.own, a.similarity)
+                .partial_cmp(&(b.own, b.similarity))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        }))
+}
+
+fn jaccard(a: &HashSet<Vec<String>>, b: &HashSet<Vec<String>>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
@@ -0,0 +1,121 @@
+use std::env;
+
+use chrono::Utc;
+use serenity::model::guild::Member;
+use serenity::model::id::{GuildId, RoleId};
+use serenity::model::prelude::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::Timestamp;
+
+use crate::discord::Discord;
+
+const DAY: i64 = 24 * 60 * 60;
+
+/// Who may send letters. Letters can be sent from DMs, so the sender is
+/// looked up in the event's guild unless they used `/sendletter` there.
+#[derive(Debug, Default)]
+pub struct Eligibility {
+    /// The event's guild, from `GUILD_ID`. Senders have to be members of it.
+    pub guild: Option<GuildId>,
+    /// How many days old a sender's Discord account has to be, from
+    /// `MIN_ACCOUNT_AGE_DAYS`.
+    pub min_account_age_days: i64,
+    /// How many days ago a sender has to have joined the guild, from
+    /// `MIN_MEMBER_DAYS`.
+    pub min_member_days: i64,
+    /// A role senders need, from `SENDER_ROLE_ID`.
+    pub sender_role: Option<RoleId>,
+}
+
+impl Eligibility {
+    /// Reads `GUILD_ID`, `MIN_ACCOUNT_AGE_DAYS`, `MIN_MEMBER_DAYS` and
+    /// `SENDER_ROLE_ID`, all of which are optional and may be left empty.
+    /// The last two need `GUILD_ID`.
+    pub fn from_env() -> Result<Self, String> {
+        let var = |name| env::var(name).ok().filter(|value| !value.is_empty());
+        let days = |name| match var(name) {
+            Some(days) => days
+                .parse::<i64>()
+                .map_err(|e| format!("{name} must be a number of days: {e}")),
+            None => Ok(0),
+        };
+
+        let eligibility = Eligibility {
+            guild: var("GUILD_ID")
+                .map(|id| id.parse().map(GuildId))
+                .transpose()
+                .map_err(|e| format!("GUILD_ID must be an integer: {e}"))?,
+            min_account_age_days: days("MIN_ACCOUNT_AGE_DAYS")?,
+            min_member_days: days("MIN_MEMBER_DAYS")?,
+            sender_role: var("SENDER_ROLE_ID")
+                .map(|id| id.parse().map(RoleId))
+                .transpose()
+                .map_err(|e| format!("SENDER_ROLE_ID must be a role ID: {e}"))?,
+        };
+
+        if eligibility.guild.is_none() {
+            if eligibility.min_member_days > 0 {
+                return Err("MIN_MEMBER_DAYS requires GUILD_ID".to_owned());
+            }
+            if eligibility.sender_role.is_some() {
+                return Err("SENDER_ROLE_ID requires GUILD_ID".to_owned());
+            }
+        }
+        Ok(eligibility)
+    }
+
+    /// Checks that whoever used `command` may send letters, and tells them
+    /// why not if they may not. Only fails if looking them up in the guild
+    /// does.
+    pub async fn check(
+        &self,
+        command: &ApplicationCommandInteraction,
+        discord: &dyn Discord,
+    ) -> serenity::Result<Result<(), String>> {
+        let now = Utc::now().timestamp();
+
+        let created = command.user.id.created_at().unix_timestamp();
+        let allowed_from = created + self.min_account_age_days * DAY;
+        if allowed_from > now {
+            return Ok(Err(format!(
+                "Your Discord account is too new to send letters. You can send them from <t:{allowed_from}:f>."
+            )));
+        }
+
+        let Some(guild) = self.guild else {
+            return Ok(Ok(()));
+        };
+        let member = match (&command.member, command.guild_id) {
+            (Some(member), Some(used_in)) if used_in == guild => Some(member.clone()),
+            _ => discord.member(guild, command.user.id).await?,
+        };
+        let Some(member) = member else {
+            return Ok(Err(
+                "Only members of the server can send letters.".to_owned()
+            ));
+        };
+
+        Ok(self.check_member(&member, now))
+    }
+
+    fn check_member(&self, member: &Member, now: i64) -> Result<(), String> {
+        // Discord always says when members joined, but if it did not they
+        // count as having just joined.
+        let joined = member
+            .joined_at
+            .as_ref()
+            .map_or(now, Timestamp::unix_timestamp);
+        let allowed_from = joined + self.min_member_days * DAY;
+        if allowed_from > now {
+            return Err(format!(
+                "You joined the server too recently to send letters. You can send them from <t:{allowed_from}:f>."
+            ));
+        }
+
+        if let Some(role) = self.sender_role {
+            if !member.roles.contains(&role) {
+                return Err("You don't have the role needed to send letters.".to_owned());
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,251 @@
+use std::env;
+use std::error::Error;
+use std::sync::RwLock;
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chrono::Utc;
+use diesel::deserialize::{self, FromSql, FromSqlRow};
+use diesel::expression::AsExpression;
+use diesel::prelude::*;
+use diesel::serialize::{self, IsNull, Output, ToSql};
+use diesel::sql_types::Text;
+use diesel::sqlite::{Sqlite, SqliteValue};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::digest::{digest, SHA256};
+use ring::rand::{SecureRandom, SystemRandom};
+use tracing::warn;
+
+/// Marks values encrypted by this module, followed by the ID of the key and
+/// the nonce and ciphertext in base64, separated by `:`. Values without it
+/// were stored before encryption was turned on.
+const PREFIX: &str = "enc:v1:";
+
+/// Marks plaintext that starts with [`PREFIX`] or with itself, so that what
+/// senders write is never mistaken for encrypted values.
+const PLAIN: &str = "plain:";
+
+/// Read instead of values that cannot be decrypted, so that one damaged
+/// letter does not keep every other one from being shown or exported. Never
+/// acted on, see [`TryEncrypted`].
+pub const UNREADABLE: &str = "[could not be decrypted]";
+
+/// The keys letters are encrypted with, from [`Keyring::from_env`] unless
+/// [`use_keyring`] was called with others.
+static KEYRING: RwLock<Keyring> = RwLock::new(Keyring {
+    current: None,
+    previous: None,
+});
+
+/// An AES-256-GCM key.
+pub struct Key {
+    /// Stored with every value encrypted with the key, to tell which key to
+    /// decrypt it with. Derived from the key, without revealing it.
+    id: String,
+    key: LessSafeKey,
+}
+
+impl Key {
+    /// Reads a key of 32 bytes in base64, like the output of
+    /// `openssl rand -base64 32`.
+    pub fn from_base64(encoded: &str) -> Result<Self, String> {
+        let bytes = BASE64
+            .decode(encoded.trim())
+            .map_err(|e| format!("not base64: {e}"))?;
+        let key = UnboundKey::new(&AES_256_GCM, &bytes)
+            .map_err(|_| format!("must be 32 bytes, not {}", bytes.len()))?;
+        let hash = digest(&SHA256, &bytes);
+        let id = hash.as_ref()[..4]
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect();
+
+        Ok(Key {
+            id,
+            key: LessSafeKey::new(key),
+        })
+    }
+
+    /// Encrypts `plaintext` with a fresh nonce.
+    pub fn seal(&self, plaintext: &str) -> String {
+        let mut nonce = [0; NONCE_LEN];
+        SystemRandom::new()
+            .fill(&mut nonce)
+            .expect("Could not generate a nonce");
+
+        let mut sealed = plaintext.as_bytes().to_vec();
+        self.key
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::empty(),
+                &mut sealed,
+            )
+            .expect("Could not encrypt");
+        sealed.splice(0..0, nonce);
+
+        format!("{PREFIX}{}:{}", self.id, BASE64.encode(sealed))
+    }
+
+    /// Decrypts the base64 part of a value sealed with this key.
+    fn open(&self, encoded: &str) -> Result<String, Box<dyn Error + Send + Sync>> {
+        let mut sealed = BASE64.decode(encoded)?;
+        if sealed.len() < NONCE_LEN {
+            return Err("encrypted value is too short".into());
+        }
+        let nonce =
+            Nonce::try_assume_unique_for_key(&sealed[..NONCE_LEN]).map_err(|_| "invalid nonce")?;
+        let plaintext = self
+            .key
+            .open_within(nonce, Aad::empty(), &mut sealed, NONCE_LEN..)
+            .map_err(|_| "could not decrypt, the value is damaged")?;
+
+        Ok(String::from_utf8(plaintext.to_vec())?)
+    }
+}
+
+/// The key new values are encrypted with, and the one used before it while
+/// letters are moved over to the new one with `rotate-key`.
+#[derive(Default)]
+pub struct Keyring {
+    pub current: Option<Key>,
+    pub previous: Option<Key>,
+}
+
+impl Keyring {
+    /// Reads `ENCRYPTION_KEY` and `PREVIOUS_ENCRYPTION_KEY`, both of which are
+    /// optional and may be left empty.
+    pub fn from_env() -> Result<Self, String> {
+        let key = |name| match env::var(name).ok().filter(|key| !key.is_empty()) {
+            Some(key) => Key::from_base64(&key)
+                .map(Some)
+                .map_err(|e| format!("{name} {e}")),
+            None => Ok(None),
+        };
+
+        Ok(Keyring {
+            current: key("ENCRYPTION_KEY")?,
+            previous: key("PREVIOUS_ENCRYPTION_KEY")?,
+        })
+    }
+
+    /// Encrypts `plaintext` with the current key, or leaves it as is without
+    /// one unless it could be mistaken for an encrypted value.
+    fn seal(&self, plaintext: &str) -> String {
+        match &self.current {
+            Some(key) => key.seal(plaintext),
+            None if plaintext.starts_with(PREFIX) || plaintext.starts_with(PLAIN) => {
+                format!("{PLAIN}{plaintext}")
+            }
+            None => plaintext.to_owned(),
+        }
+    }
+
+    fn open(&self, stored: &str) -> Result<String, Box<dyn Error + Send + Sync>> {
+        if let Some(plaintext) = stored.strip_prefix(PLAIN) {
+            return Ok(plaintext.to_owned());
+        }
+        let Some(sealed) = stored.strip_prefix(PREFIX) else {
+            return Ok(stored.to_owned());
+        };
+        let (id, encoded) = sealed
+            .split_once(':')
+            .ok_or("encrypted value has no key ID")?;
+        let key = [&self.current, &self.previous]
+            .into_iter()
+            .flatten()
+            .find(|key| key.id == id)
+            .ok_or_else(|| format!("value was encrypted with key {id}, which is not configured"))?;
+
+        key.open(encoded)
+    }
+}
+
+/// Replaces the keys letters are encrypted with.
+pub fn use_keyring(keyring: Keyring) {
+    *KEYRING.write().unwrap() = keyring;
+}
+
+/// Text that is encrypted in the database, if there is a key. Holds the
+/// plaintext, for use with `serialize_as` and `deserialize_as` on model
+/// fields, so the rest of the bot never sees the ciphertext.
+#[derive(Debug, Clone, PartialEq, Eq, AsExpression, FromSqlRow)]
+#[diesel(sql_type = Text)]
+pub struct Encrypted(pub String);
+
+impl From<&str> for Encrypted {
+    fn from(plaintext: &str) -> Self {
+        Encrypted(plaintext.to_owned())
+    }
+}
+
+impl From<Encrypted> for String {
+    fn from(encrypted: Encrypted) -> Self {
+        encrypted.0
+    }
+}
+
+impl ToSql<Text, Sqlite> for Encrypted {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Sqlite>) -> serialize::Result {
+        out.set_value(KEYRING.read().unwrap().seal(&self.0));
+        Ok(IsNull::No)
+    }
+}
+
+impl FromSql<Text, Sqlite> for Encrypted {
+    fn from_sql(value: SqliteValue<'_, '_, '_>) -> deserialize::Result<Self> {
+        let stored = <String as FromSql<Text, Sqlite>>::from_sql(value)?;
+        let plaintext = KEYRING.read().unwrap().open(&stored).unwrap_or_else(|why| {
+            warn!("Could not decrypt a letter: {why}");
+            UNREADABLE.to_owned()
+        });
+        Ok(Encrypted(plaintext))
+    }
+}
+
+/// Like [`Encrypted`], but fails to load values that cannot be decrypted
+/// instead of reading them as [`UNREADABLE`], for queries that act on what
+/// they read, like blocking a sender or counting their letters.
+#[derive(Debug, Clone, PartialEq, Eq, FromSqlRow)]
+pub struct TryEncrypted(pub String);
+
+impl FromSql<Text, Sqlite> for TryEncrypted {
+    fn from_sql(value: SqliteValue<'_, '_, '_>) -> deserialize::Result<Self> {
+        let stored = <String as FromSql<Text, Sqlite>>::from_sql(value)?;
+        let plaintext = KEYRING.read().unwrap().open(&stored)?;
+        Ok(TryEncrypted(plaintext))
+    }
+}
+
+/// Encrypts the content and sender of every letter with the current key,
+/// whether they were stored in plaintext or with the previous key, and
+/// returns how many letters there are. Without a current key, decrypts them.
+/// Changes nothing if any letter cannot be decrypted.
+pub fn rotate(conn: &mut SqliteConnection) -> QueryResult<usize> {
+    use crate::schema::letters::dsl::{content, id, letters, sender, sender_id, updated_at};
+
+    let now = Utc::now().naive_utc();
+    conn.transaction(|conn| {
+        let stored: Vec<(i32, String, String, String)> = letters
+            .select((id, content, sender, sender_id))
+            .load(conn)?;
+        for (letter_id, letter_content, letter_sender, letter_sender_id) in &stored {
+            let open = |stored: &str| {
+                let opened = KEYRING.read().unwrap().open(stored);
+                opened.map(Encrypted).map_err(|why| {
+                    diesel::result::Error::DeserializationError(
+                        format!("letter {letter_id} could not be decrypted: {why}").into(),
+                    )
+                })
+            };
+            diesel::update(letters.find(letter_id))
+                .set((
+                    content.eq(open(letter_content)?),
+                    sender.eq(open(letter_sender)?),
+                    sender_id.eq(open(letter_sender_id)?),
+                    updated_at.eq(now),
+                ))
+                .execute(conn)?;
+        }
+        Ok(stored.len())
+    })
+}
@@ -0,0 +1,117 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use chrono::SecondsFormat;
+use clap::ValueEnum;
+use diesel::prelude::*;
+use serde::Serialize;
+
+use crate::model::Letter;
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum ExportFormat {
+    Json,
+    Csv,
+    Markdown,
+}
+
+impl ExportFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        <Self as ValueEnum>::from_str(name, true).ok()
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Json => "json",
+            ExportFormat::Csv => "csv",
+            ExportFormat::Markdown => "md",
+        }
+    }
+}
+
+/// A letter as it appears in an export. When redacting, anonymous letters
+/// lose their sender so the file can be shared outside the mod team.
+#[derive(Serialize)]
+struct ExportedLetter<'a> {
+    id: i32,
+    recipient: &'a str,
+    sender: Option<&'a str>,
+    sender_id: Option<&'a str>,
+    anon: bool,
+    content: &'a str,
+    /// When the letter was sent, in RFC 3339, so imports keep the order.
+    created_at: Option<String>,
+}
+
+impl<'a> ExportedLetter<'a> {
+    fn new(letter: &'a Letter, redact: bool) -> Self {
+        let hide_sender = redact && letter.anon;
+        Self {
+            id: letter.id,
+            recipient: &letter.recipient,
+            sender: (!hide_sender).then_some(letter.sender.as_str()),
+            sender_id: (!hide_sender).then_some(letter.sender_id.as_str()),
+            anon: letter.anon,
+            content: &letter.content,
+            created_at: letter
+                .created_at
+                .map(|sent| sent.and_utc().to_rfc3339_opts(SecondsFormat::Millis, true)),
+        }
+    }
+}
+
+pub fn load_letters(conn: &mut SqliteConnection) -> QueryResult<Vec<Letter>> {
+    use crate::schema::letters::dsl::{id, letters};
+
+    letters.order(id.asc()).load(conn)
+}
+
+pub fn export(letters: &[Letter], format: ExportFormat, redact: bool) -> Result<Vec<u8>, String> {
+    let exported = letters
+        .iter()
+        .map(|letter| ExportedLetter::new(letter, redact))
+        .collect::<Vec<_>>();
+
+    match format {
+        ExportFormat::Json => serde_json::to_vec_pretty(&exported)
+            .map_err(|e| format!("Error while writing JSON: {e}")),
+        ExportFormat::Csv => to_csv(&exported).map_err(|e| format!("Error while writing CSV: {e}")),
+        ExportFormat::Markdown => Ok(to_markdown(&exported).into_bytes()),
+    }
+}
+
+fn to_csv(letters: &[ExportedLetter]) -> Result<Vec<u8>, csv::Error> {
+    let mut writer = csv::Writer::from_writer(vec![]);
+    for letter in letters {
+        writer.serialize(letter)?;
+    }
+    writer.into_inner().map_err(|e| e.into_error().into())
+}
+
+fn to_markdown(letters: &[ExportedLetter]) -> String {
+    let mut by_recipient: BTreeMap<&str, Vec<&ExportedLetter>> = BTreeMap::new();
+    for letter in letters {
+        by_recipient
+            .entry(letter.recipient)
+            .or_default()
+            .push(letter);
+    }
+
+    let mut out = String::from("# 2023 Classroom of the Elite Valentine's Event\n");
+    for (recipient, letters) in by_recipient {
+        let _ = write!(out, "\n## To {recipient}\n");
+        for letter in letters {
+            let from = match (letter.sender, letter.anon) {
+                (None, _) => "Anonymous".to_owned(),
+                (Some(sender), true) => format!("{sender} (anonymous)"),
+                (Some(sender), false) => sender.to_owned(),
+            };
+            let _ = write!(
+                out,
+                "\n### Letter #{} from {from}\n\n{}\n",
+                letter.id, letter.content
+            );
+        }
+    }
+    out
+}
@@ -0,0 +1,218 @@
+use std::sync::LazyLock;
+use std::{env, fs};
+
+use regex::{Regex, RegexBuilder};
+use serde::Deserialize;
+
+/// What the filter looks for, loaded from the JSON file in `CONTENT_FILTER`.
+/// Everything is off by default.
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct FilterConfig {
+    /// Letters breaking these rules are not accepted.
+    pub reject: RuleConfig,
+    /// Letters breaking these rules are accepted, but marked for review in
+    /// the audit channel.
+    pub flag: RuleConfig,
+    /// Removes user, role, `@everyone` and `@here` mentions from letters.
+    pub strip_mentions: bool,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct RuleConfig {
+    /// Whole words, ignoring case.
+    pub words: Vec<String>,
+    /// Regular expressions.
+    pub patterns: Vec<String>,
+    /// Web links.
+    pub links: bool,
+    /// Discord server invites.
+    pub invites: bool,
+}
+
+/// Screens letters before they are stored.
+#[derive(Default)]
+pub struct ContentFilter {
+    reject: Rules,
+    flag: Rules,
+    strip_mentions: bool,
+}
+
+#[derive(Default)]
+struct Rules {
+    words: Option<Regex>,
+    patterns: Vec<Regex>,
+    links: bool,
+    invites: bool,
+}
+
+/// A letter the filter let through.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Screened {
+    /// The letter, without mentions if they are stripped.
+    pub letter: String,
+    /// Why the letter needs another look, if it does.
+    pub flags: Vec<String>,
+}
+
+static INVITE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)\b(?:discord(?:app)?\.com/invite|discord\.gg)/[\w-]+").unwrap()
+});
+static LINK: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)\b(?:https?://|www\.)\S+").unwrap());
+/// A mention, along with the space before it.
+static MENTION: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r" ?(?:<@[!&]?\d+>|@everyone|@here)").unwrap());
+/// The `@` of a mention, and what follows it.
+static MENTION_AT: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(<)?@([!&]?\d+>|everyone|here)").unwrap());
+
+impl Rules {
+    fn new(config: RuleConfig) -> Result<Self, regex::Error> {
+        let words = if config.words.is_empty() {
+            None
+        } else {
+            let words = config
+                .words
+                .iter()
+                .map(|word| regex::escape(word))
+                .collect::<Vec<_>>()
+                .join("|");
+            Some(
+                RegexBuilder::new(&format!(r"\b(?:{words})\b"))
+                    .case_insensitive(true)
+                    .build()?,
+            )
+        };
+
+        Ok(Rules {
+            words,
+            patterns: config
+                .patterns
+                .iter()
+                .map(|pattern| Regex::new(pattern))
+                .collect::<Result<_, _>>()?,
+            links: config.links,
+            invites: config.invites,
+        })
+    }
+
+    /// Every rule the letter breaks.
+    fn broken_by(&self, letter: &str) -> Vec<Broken> {
+        let mut broken = Vec::new();
+
+        if let Some(word) = self.words.as_ref().and_then(|words| words.find(letter)) {
+            broken.push(Broken::Word(word.as_str().to_owned()));
+        }
+        for pattern in &self.patterns {
+            if pattern.is_match(letter) {
+                broken.push(Broken::Pattern(pattern.to_string()));
+            }
+        }
+        if self.invites && INVITE.is_match(letter) {
+            broken.push(Broken::Invite);
+        }
+        // invites are links as well, but they are only reported as invites
+        if self.links && LINK.is_match(&INVITE.replace_all(letter, "")) {
+            broken.push(Broken::Link);
+        }
+
+        broken
+    }
+}
+
+/// A rule broken by a letter.
+enum Broken {
+    Word(String),
+    Pattern(String),
+    Invite,
+    Link,
+}
+
+impl Broken {
+    /// Why a letter was rejected, for its sender. Leaves out what matched, as
+    /// this ends up in the logs, which never contain letters.
+    fn rejection(&self) -> &'static str {
+        match self {
+            Broken::Word(_) => "contains a word that is not allowed",
+            Broken::Pattern(_) => "contains something that is not allowed",
+            Broken::Invite => "contains a server invite",
+            Broken::Link => "contains a link",
+        }
+    }
+}
+
+/// Why a letter was flagged, for moderators.
+impl std::fmt::Display for Broken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Broken::Word(word) => write!(f, "contains the word \"{word}\""),
+            Broken::Pattern(pattern) => write!(f, "matches `{pattern}`"),
+            Broken::Invite => f.write_str("contains a server invite"),
+            Broken::Link => f.write_str("contains a link"),
+        }
+    }
+}
+
+impl ContentFilter {
+    pub fn new(config: FilterConfig) -> Result<Self, regex::Error> {
+        Ok(ContentFilter {
+            reject: Rules::new(config.reject)?,
+            flag: Rules::new(config.flag)?,
+            strip_mentions: config.strip_mentions,
+        })
+    }
+
+    /// Loads the filter configured in the file named by `CONTENT_FILTER`, or a
+    /// filter that lets everything through if there is none.
+    pub fn from_env() -> Result<Self, String> {
+        let Some(path) = env::var("CONTENT_FILTER")
+            .ok()
+            .filter(|path| !path.is_empty())
+        else {
+            return Ok(ContentFilter::default());
+        };
+        let config = fs::read_to_string(&path)
+            .map_err(|e| format!("Could not read content filter {path}: {e}"))?;
+        let config = serde_json::from_str(&config)
+            .map_err(|e| format!("Invalid content filter {path}: {e}"))?;
+        ContentFilter::new(config).map_err(|e| format!("Invalid pattern in {path}: {e}"))
+    }
+
+    /// Checks a letter, returning why it was rejected if it was.
+    pub fn screen(&self, letter: &str) -> Result<Screened, String> {
+        let letter = if self.strip_mentions {
+            strip_mentions(letter)
+        } else {
+            letter.to_owned()
+        };
+
+        if let Some(broken) = self.reject.broken_by(&letter).first() {
+            return Err(broken.rejection().to_owned());
+        }
+
+        Ok(Screened {
+            flags: self
+                .flag
+                .broken_by(&letter)
+                .iter()
+                .map(Broken::to_string)
+                .collect(),
+            letter,
+        })
+    }
+}
+
+/// Removes user, role, `@everyone` and `@here` mentions.
+pub fn strip_mentions(text: &str) -> String {
+    MENTION.replace_all(text, "").trim_start().to_owned()
+}
+
+/// Keeps mentions from working by putting a zero width space after their `@`,
+/// so they show up as typed instead of pinging anyone.
+pub fn neutralize_mentions(text: &str) -> String {
+    MENTION_AT
+        .replace_all(text, "${1}@\u{200B}${2}")
+        .into_owned()
+}
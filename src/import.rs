@@ -0,0 +1,251 @@
+use std::collections::HashSet;
+use std::fmt::{self, Write};
+use std::path::Path;
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use clap::ValueEnum;
+use diesel::prelude::*;
+use serde::Deserialize;
+
+use crate::commands::send::ValentineLetter;
+use crate::model::{NewLetter, Recipient, NO_SENDER_ID};
+
+/// The start of 2015, which Discord IDs count from.
+const DISCORD_EPOCH_MILLIS: i64 = 1_420_070_400_000;
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum ImportFormat {
+    Json,
+    Csv,
+}
+
+impl ImportFormat {
+    /// Guesses the format from a file name.
+    pub fn from_filename(filename: &str) -> Option<Self> {
+        let extension = Path::new(filename).extension()?.to_str()?;
+        <Self as ValueEnum>::from_str(extension, true).ok()
+    }
+}
+
+/// One letter in an import file. The field names match the export, with the
+/// `/sendletter` option names accepted as well.
+#[derive(Deserialize)]
+struct ImportRow {
+    recipient: String,
+    sender: String,
+    /// Missing for letters collected outside Discord, which are stored with
+    /// [`NO_SENDER_ID`].
+    #[serde(default)]
+    sender_id: String,
+    #[serde(alias = "anonymous")]
+    anon: bool,
+    #[serde(alias = "letter")]
+    content: String,
+    /// When the letter was sent, so imported letters keep their place among
+    /// the others.
+    #[serde(default)]
+    created_at: Option<String>,
+    /// The message the letter was logged in, which says when it was sent if
+    /// `created_at` is missing.
+    #[serde(default)]
+    message_id: Option<String>,
+    /// Only used when the recipient is not known yet.
+    #[serde(default)]
+    recipient_is_real: bool,
+}
+
+impl ImportRow {
+    /// When the letter was sent, from `created_at` or else `message_id`, or
+    /// now if the row has neither.
+    fn sent_at(&self) -> Result<NaiveDateTime, String> {
+        let non_empty = |value: &Option<String>| value.clone().filter(|value| !value.is_empty());
+
+        if let Some(created_at) = non_empty(&self.created_at) {
+            return DateTime::parse_from_rfc3339(&created_at)
+                .map(|sent| sent.naive_utc())
+                .or_else(|_| created_at.parse::<NaiveDateTime>())
+                .map_err(|e| format!("Invalid created_at {created_at:?}: {e}"));
+        }
+        if let Some(message_id) = non_empty(&self.message_id) {
+            let id: u64 = message_id
+                .parse()
+                .map_err(|e| format!("Invalid message_id {message_id:?}: {e}"))?;
+            // Discord IDs start with the milliseconds since Discord's epoch
+            let millis = (id >> 22) as i64 + DISCORD_EPOCH_MILLIS;
+            return DateTime::from_timestamp_millis(millis)
+                .map(|sent| sent.naive_utc())
+                .ok_or_else(|| format!("Invalid message_id {message_id:?}"));
+        }
+        Ok(Utc::now().naive_utc())
+    }
+}
+
+/// Why a file could not be imported at all. Invalid rows are skipped and
+/// listed in the [`ImportReport`] instead.
+#[derive(Debug)]
+pub enum ImportError {
+    /// The file is not valid JSON or CSV.
+    Parse(String),
+    /// Reading recipients or inserting letters failed.
+    Query(diesel::result::Error),
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::Parse(message) => f.write_str(message),
+            ImportError::Query(e) => write!(f, "Error while importing: {e}"),
+        }
+    }
+}
+
+impl From<diesel::result::Error> for ImportError {
+    fn from(e: diesel::result::Error) -> Self {
+        ImportError::Query(e)
+    }
+}
+
+pub struct RowError {
+    /// 1-based index of the letter in the file.
+    pub row: usize,
+    pub message: String,
+}
+
+#[derive(Default)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub letters: usize,
+    pub recipients: usize,
+    pub errors: Vec<RowError>,
+}
+
+impl ImportReport {
+    /// Summarises the import, listing at most `max_errors` row errors.
+    pub fn summary(&self, max_errors: usize) -> String {
+        let mut out = format!(
+            "{} {} letters and {} new recipients",
+            if self.dry_run {
+                "Would import"
+            } else {
+                "Imported"
+            },
+            self.letters,
+            self.recipients
+        );
+
+        if !self.errors.is_empty() {
+            let _ = write!(out, ", skipped {} rows:", self.errors.len());
+            for error in self.errors.iter().take(max_errors) {
+                let _ = write!(out, "\nRow {}: {}", error.row, error.message);
+            }
+            if self.errors.len() > max_errors {
+                let _ = write!(out, "\n...and {} more", self.errors.len() - max_errors);
+            }
+        }
+        out
+    }
+}
+
+fn parse(data: &[u8], format: ImportFormat) -> Result<Vec<Result<ImportRow, String>>, ImportError> {
+    match format {
+        ImportFormat::Json => {
+            let rows: Vec<serde_json::Value> = serde_json::from_slice(data)
+                .map_err(|e| ImportError::Parse(format!("Invalid JSON: {e}")))?;
+            Ok(rows
+                .into_iter()
+                .map(|row| serde_json::from_value(row).map_err(|e| e.to_string()))
+                .collect())
+        }
+        ImportFormat::Csv => {
+            let mut reader = csv::Reader::from_reader(data);
+            Ok(reader
+                .deserialize()
+                .map(|row| row.map_err(|e| e.to_string()))
+                .collect())
+        }
+    }
+}
+
+/// Validates every row and inserts the valid ones, along with any recipients
+/// that are not known yet. Nothing is written on a dry run.
+pub fn import(
+    conn: &mut SqliteConnection,
+    data: &[u8],
+    format: ImportFormat,
+    dry_run: bool,
+) -> Result<ImportReport, ImportError> {
+    use crate::schema::letters::dsl::letters;
+    use crate::schema::recipients::dsl::{fullname, recipients};
+
+    let mut report = ImportReport {
+        dry_run,
+        ..Default::default()
+    };
+
+    let mut valid = vec![];
+    for (row, parsed) in (1..).zip(parse(data, format)?) {
+        let checked = parsed.and_then(|row| {
+            let is_real = row.recipient_is_real;
+            let sent_at = row.sent_at()?;
+            let letter = ValentineLetter {
+                sender: row.sender,
+                sender_id: if row.sender_id.is_empty() {
+                    NO_SENDER_ID.to_owned()
+                } else {
+                    row.sender_id
+                },
+                recipient: row.recipient,
+                letter: row.content,
+                anon: row.anon,
+                sent_at,
+                guild_id: None,
+                channel_id: None,
+            };
+            letter.validate().map_err(|e| e.to_string())?;
+            Ok((letter, is_real))
+        });
+
+        match checked {
+            Ok(letter) => valid.push(letter),
+            Err(message) => report.errors.push(RowError { row, message }),
+        }
+    }
+
+    conn.transaction(|conn| {
+        let mut known: HashSet<String> = recipients
+            .select(fullname)
+            .load::<String>(conn)?
+            .into_iter()
+            .collect();
+
+        let new_recipients = valid
+            .iter()
+            .filter(|(letter, _)| known.insert(letter.recipient.clone()))
+            .map(|(letter, is_real)| Recipient {
+                fullname: letter.recipient.clone(),
+                is_real: *is_real,
+            })
+            .collect::<Vec<_>>();
+
+        let new_letters = valid
+            .iter()
+            .map(|(letter, _)| NewLetter::new(letter, None))
+            .collect::<Vec<_>>();
+
+        report.recipients = new_recipients.len();
+        report.letters = new_letters.len();
+
+        if !dry_run {
+            diesel::insert_into(recipients)
+                .values(&new_recipients)
+                .execute(conn)?;
+            diesel::insert_into(letters)
+                .values(new_letters)
+                .execute(conn)?;
+        }
+
+        Ok::<_, ImportError>(())
+    })?;
+
+    Ok(report)
+}
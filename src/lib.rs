@@ -0,0 +1,41 @@
+pub mod archive;
+pub mod audit;
+pub mod blocklist;
+pub mod card;
+pub mod chart;
+pub mod cli;
+pub mod commands;
+pub mod cooldown;
+pub mod discord;
+pub mod draw;
+pub mod duplicates;
+pub mod eligibility;
+pub mod encryption;
+pub mod export;
+pub mod filter;
+pub mod import;
+pub mod logging;
+pub mod metrics;
+pub mod model;
+pub mod permissions;
+pub mod podcast;
+pub mod repository;
+pub mod schema;
+pub mod stats;
+pub mod votes;
+
+use diesel::sqlite::Sqlite;
+use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+
+pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("./migrations");
+pub fn run_migrations(
+    connection: &mut impl MigrationHarness<Sqlite>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    // This will run the necessary migrations.
+    //
+    // See the documentation for `MigrationHarness` for
+    // all available methods.
+    connection.run_pending_migrations(MIGRATIONS)?;
+
+    Ok(())
+}
@@ -0,0 +1,35 @@
+use std::env;
+
+use tracing::{warn, Span};
+use tracing_subscriber::EnvFilter;
+
+const DEFAULT_LEVEL: &str = "info";
+
+/// Sets up logging to stdout.
+///
+/// `LOG_LEVEL` takes a level or a list of directives such as
+/// `info,cotevalentines=debug`, and `LOG_FORMAT=json` switches to one JSON
+/// object per line. Letter contents are never logged, only their IDs.
+pub fn init() {
+    let level = env::var("LOG_LEVEL").unwrap_or_else(|_| DEFAULT_LEVEL.to_owned());
+    let (filter, invalid) = match EnvFilter::try_new(&level) {
+        Ok(filter) => (filter, None),
+        Err(e) => (EnvFilter::new(DEFAULT_LEVEL), Some(e)),
+    };
+
+    let subscriber = tracing_subscriber::fmt().with_env_filter(filter);
+    if env::var("LOG_FORMAT").is_ok_and(|format| format.eq_ignore_ascii_case("json")) {
+        subscriber.json().init();
+    } else {
+        subscriber.init();
+    }
+
+    if let Some(e) = invalid {
+        warn!("Invalid LOG_LEVEL {level:?}, logging at {DEFAULT_LEVEL}: {e}");
+    }
+}
+
+/// Records the letter the current interaction is about on its span.
+pub fn record_letter(id: i32) {
+    Span::current().record("letter_id", id);
+}
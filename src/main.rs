@@ -1,218 +1,260 @@
-pub mod commands;
-pub mod model;
-pub mod schema;
-
+use cotevalentines::repository::{self, LetterRepository};
+use cotevalentines::{
+    cli, commands, cooldown, eligibility, encryption, filter, logging, metrics, permissions,
+    run_migrations,
+};
 use diesel::r2d2::{ConnectionManager, Pool};
-use diesel::sqlite::{Sqlite, SqliteConnection};
-use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
-
-pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("./migrations");
-fn run_migrations(
-    connection: &mut impl MigrationHarness<Sqlite>,
-) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
-    // This will run the necessary migrations.
-    //
-    // See the documentation for `MigrationHarness` for
-    // all available methods.
-    connection.run_pending_migrations(MIGRATIONS)?;
-
-    Ok(())
-}
+use diesel::sqlite::SqliteConnection;
 
+use clap::Parser;
 use dotenv::dotenv;
 use std::env;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
 
 use serenity::async_trait;
+use serenity::client::bridge::gateway::event::ShardStageUpdateEvent;
+use serenity::gateway::ConnectionStage;
 use serenity::model::application::interaction::{Interaction, InteractionResponseType};
+use serenity::model::event::ResumedEvent;
 use serenity::model::gateway::Ready;
-use serenity::model::prelude::command::Command;
+use serenity::model::id::UserId;
 use serenity::prelude::*;
+use tracing::{debug, error, field, info, info_span, Instrument};
 
 pub struct Handler {
-    db_pool: Pool<ConnectionManager<SqliteConnection>>,
-    letters_allowed: AtomicBool,
+    db: repository::Repository,
+    commands: commands::Registry,
+    command_scopes: commands::registration::Scopes,
+    access: permissions::Access,
 }
 
-#[async_trait]
-impl EventHandler for Handler {
-    async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
-        #![allow(clippy::single_match)]
+impl Handler {
+    async fn dispatch(
+        &self,
+        ctx: &Context,
+        interaction: &mut Interaction,
+    ) -> Result<(), commands::CommandError> {
+        use commands::CommandError;
+
         match interaction {
             Interaction::ApplicationCommand(command) => {
-                // println!("Received command interaction: {:#?}", command);
-
-                use commands::{add_recipient, allow_letters, publish, send};
-
-                let result = match command.data.name.as_str() {
-                    // "ping" => commands::ping::run(&command.data.options),
-                    "sendletter" if self.letters_allowed.load(Ordering::SeqCst) => {
-                        send::run(&command, &ctx, &mut self.db_pool.get().unwrap()).await
-                    }
-                    "sendletter" if !self.letters_allowed.load(Ordering::SeqCst) => {
-                        send::forbidden().await
-                    }
-                    "publish" => {
-                        publish::run(&command, &ctx, &mut self.db_pool.get().unwrap()).await
-                    }
-                    "add_recipient" => {
-                        add_recipient::run(&command, &ctx, &mut self.db_pool.get().unwrap()).await
-                    }
-                    "allow_letters" => {
-                        allow_letters::run(&command, &ctx, &mut self.db_pool.get().unwrap())
-                            .await
-                            .map(|opt| {
-                                opt.map(|(allowed, ret)| {
-                                    self.letters_allowed.store(allowed, Ordering::SeqCst);
-                                    ret
+                let handler = self
+                    .commands
+                    .command(&command.data.name)
+                    .ok_or(CommandError::Malformed("command not found"))?;
+                if let Some(action) = handler.action() {
+                    self.access.check(command.member.as_ref(), action)?;
+                }
+
+                let content = handler.run(command, ctx, &self.db).await?;
+                if let Some(content) = content {
+                    command
+                        .create_interaction_response(&ctx.http, |response| {
+                            response
+                                .kind(InteractionResponseType::ChannelMessageWithSource)
+                                .interaction_response_data(|message| {
+                                    message.content(content).ephemeral(true)
                                 })
-                            })
-                    }
-                    _ => Err("command not found".to_string()),
-                };
-
-                match result {
-                    Ok(None) => (),
-                    Ok(Some(content)) | Err(content) => {
-                        if let Err(why) = command
-                            .create_interaction_response(&ctx.http, |response| {
-                                response
-                                    .kind(InteractionResponseType::ChannelMessageWithSource)
-                                    .interaction_response_data(|message| {
-                                        message.content(content).ephemeral(true)
-                                    })
-                            })
-                            .await
-                        {
-                            println!("Cannot respond to slash command: {why}");
-                        }
-                    }
-                };
+                        })
+                        .await?;
+                }
+                Ok(())
             }
             Interaction::MessageComponent(interaction) => {
-                match interaction.data.custom_id.as_str() {
-                    "delete_letter" => commands::delete::handle_button(&interaction, &ctx).await,
-                    custom_id => println!("Message component interaction not found: {custom_id}"),
-                };
-                // println!("a message component interaction arrived: {interaction:?}");
+                let owner = self.commands.owner_of(&interaction.data.custom_id).ok_or(
+                    CommandError::Malformed("message component interaction not found"),
+                )?;
+                if let Some(action) = owner.component_action(&interaction.data.custom_id) {
+                    self.access.check(interaction.member.as_ref(), action)?;
+                }
+                owner.component(interaction, ctx, &self.db).await
             }
-            Interaction::ModalSubmit(mut interaction) => {
-                match interaction.data.custom_id.as_str() {
-                    "delete_modal" => {
-                        commands::delete::handle_modal(
-                            &mut interaction,
-                            &ctx,
-                            &mut self.db_pool.get().unwrap(),
-                        )
-                        .await
-                    }
-                    _ => (),
+            Interaction::ModalSubmit(interaction) => {
+                let owner = self
+                    .commands
+                    .owner_of(&interaction.data.custom_id)
+                    .ok_or(CommandError::Malformed("modal not found"))?;
+                if let Some(action) = owner.component_action(&interaction.data.custom_id) {
+                    self.access.check(interaction.member.as_ref(), action)?;
                 }
+                owner.modal(interaction, ctx, &self.db).await
             }
             Interaction::Autocomplete(interaction) => {
-                commands::send::complete(&interaction, &ctx, &mut self.db_pool.get().unwrap())
+                self.commands
+                    .command(&interaction.data.name)
+                    .ok_or(CommandError::Malformed("command not found"))?
+                    .autocomplete(interaction, ctx, &self.db)
                     .await
-                    .unwrap();
             }
-            _ => (),
+            _ => Ok(()),
         }
     }
+}
+
+#[async_trait]
+impl EventHandler for Handler {
+    async fn interaction_create(&self, ctx: Context, mut interaction: Interaction) {
+        let started = Instant::now();
+        let (kind, name, guild_id, user_id) = match &interaction {
+            Interaction::ApplicationCommand(command) => (
+                "command",
+                command.data.name.clone(),
+                command.guild_id,
+                command.user.id,
+            ),
+            Interaction::MessageComponent(interaction) => (
+                "component",
+                interaction.data.custom_id.clone(),
+                interaction.guild_id,
+                interaction.user.id,
+            ),
+            Interaction::ModalSubmit(interaction) => (
+                "modal",
+                interaction.data.custom_id.clone(),
+                interaction.guild_id,
+                interaction.user.id,
+            ),
+            Interaction::Autocomplete(interaction) => (
+                "autocomplete",
+                interaction.data.name.clone(),
+                interaction.guild_id,
+                interaction.user.id,
+            ),
+            _ => ("other", String::new(), None, UserId(0)),
+        };
+
+        let span = info_span!(
+            "interaction",
+            kind,
+            name,
+            guild_id = guild_id.map(|id| id.0),
+            user_id = user_id.0,
+            letter_id = field::Empty,
+        );
+        async {
+            if let Err(e) = self.dispatch(&ctx, &mut interaction).await {
+                commands::report(&ctx, &interaction, e).await;
+            }
+            debug!(elapsed = ?started.elapsed(), "Handled interaction");
+        }
+        .instrument(span)
+        .await;
+
+        metrics::INTERACTION_DURATION
+            .with_label_values(&[kind, &name])
+            .observe(started.elapsed().as_secs_f64());
+    }
+
+    async fn resume(&self, _ctx: Context, _: ResumedEvent) {
+        metrics::GATEWAY_CONNECTED.set(1);
+    }
+
+    async fn shard_stage_update(&self, _ctx: Context, event: ShardStageUpdateEvent) {
+        metrics::GATEWAY_CONNECTED.set((event.new == ConnectionStage::Connected).into());
+    }
 
     async fn ready(&self, ctx: Context, ready: Ready) {
-        println!("{} is connected!", ready.user.name);
-
-        // let guild_id = GuildId(
-        //     env::var("GUILD_ID")
-        //         .expect("Expected GUILD_ID in environment")
-        //         .parse()
-        //         .expect("GUILD_ID must be an integer"),
-        // );
-
-        // guild_id
-        //     .delete_application_command(&ctx.http, CommandId(1070794475665891348))
-        //     .unwrap_or_else(|f| {
-        //         println!("Shit's fucked {}", f.to_string());
-        //     })
-        //     .await;
-
-        let commands = Command::set_global_application_commands(&ctx.http, |commands| {
-            commands
-                .create_application_command(|command| commands::send::register(command))
-                .create_application_command(|command| commands::publish::register(command))
-                .create_application_command(|command| commands::add_recipient::register(command))
-                .create_application_command(|command| commands::allow_letters::register(command))
-        })
+        info!("{} is connected!", ready.user.name);
+        metrics::GATEWAY_CONNECTED.set(1);
+
+        if let Err(why) = commands::registration::sync(
+            &ctx.http,
+            &self.commands,
+            &self.access,
+            self.command_scopes,
+        )
         .await
-        .expect("able to set application commands");
-
-        println!(
-            "I now have the following guild slash commands: {}",
-            commands
-                .iter()
-                .map(|command| format!(
-                    "\n- \"{}\" ({} options): {}",
-                    command.name,
-                    command.options.len(),
-                    command.description
-                ))
-                .reduce(|acc, val| acc + &val)
-                .unwrap()
-        );
+        {
+            error!("Could not register slash commands: {why}");
+        }
     }
 }
 
 #[tokio::main]
 async fn main() {
     dotenv().ok();
-    // Configure the client with your Discord bot token in the environment.
-    let token = env::var("DISCORD_TOKEN").expect("Expected a token in the environment");
+    let cli = cli::Cli::parse();
+    logging::init();
 
     let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    encryption::use_keyring(encryption::Keyring::from_env().unwrap_or_else(|why| panic!("{why}")));
 
     {
         use diesel::prelude::*;
-        use model::Recipient;
-        use schema::recipients::dsl::recipients;
 
         let conn = &mut SqliteConnection::establish(&database_url).unwrap();
 
         run_migrations(conn).unwrap();
 
-        if let Ok(var) = env::var("RECIPIENTS") {
-            _ = diesel::delete(recipients).execute(conn).unwrap();
-            diesel::insert_into(recipients)
-                .values(
-                    var.split(':')
-                        .map(|name| Recipient {
-                            fullname: name.replace('_', " "),
-                            is_real: false,
-                        })
-                        .collect::<Vec<_>>(),
-                )
-                .execute(conn)
-                .unwrap();
-        } else {
-            println!("No default recipients specified, not resetting database.")
+        if let Some(command) = cli.command {
+            if let Err(why) = cli::run(command, conn) {
+                eprintln!("{why}");
+                std::process::exit(1);
+            }
+            return;
         }
     }
 
+    let command_scopes =
+        commands::registration::Scopes::from_env().unwrap_or_else(|why| panic!("{why}"));
+
+    let content_filter = filter::ContentFilter::from_env().unwrap_or_else(|why| panic!("{why}"));
+    let cooldown = cooldown::Cooldown::from_env().unwrap_or_else(|why| panic!("{why}"));
+    let eligibility = eligibility::Eligibility::from_env().unwrap_or_else(|why| panic!("{why}"));
+    let access = permissions::Access::from_env().unwrap_or_else(|why| panic!("{why}"));
+
+    // Configure the client with your Discord bot token in the environment.
+    let token = env::var("DISCORD_TOKEN").expect("Expected a token in the environment");
+
+    let db = repository::Repository::new(
+        Pool::builder()
+            .test_on_check_out(true)
+            .build(ConnectionManager::<SqliteConnection>::new(database_url))
+            .expect("Could not build connection pool"),
+    );
+    if let Ok(var) = env::var("RECIPIENTS") {
+        db.seed_recipients(repository::parse_recipients(&var))
+            .await
+            .expect("Could not reset recipients");
+    } else {
+        info!("No default recipients specified, not resetting database.");
+        db.reload_recipients()
+            .await
+            .expect("Could not load recipients");
+    }
+    let dashboard = commands::stats::load_dashboard(&db)
+        .await
+        .expect("Could not load the statistics dashboard");
+
     // Build our client.
     let mut client = Client::builder(token, GatewayIntents::empty())
         .event_handler(Handler {
-            db_pool: Pool::builder()
-                .test_on_check_out(true)
-                .build(ConnectionManager::<SqliteConnection>::new(database_url))
-                .expect("Could not build connection pool"),
-            letters_allowed: AtomicBool::new(true),
+            db: db.clone(),
+            commands: commands::registry(dashboard.clone(), content_filter, cooldown, eligibility),
+            command_scopes,
+            access,
         })
         .await
         .expect("Error creating client");
 
+    if let Ok(addr) = env::var("METRICS_ADDR") {
+        let addr = addr
+            .parse()
+            .expect("METRICS_ADDR must be an address like 0.0.0.0:9090");
+        tokio::spawn(metrics::serve(addr, db.pool().clone()));
+    }
+
+    tokio::spawn(commands::stats::keep_dashboard_updated(
+        client.cache_and_http.http.clone(),
+        db,
+        dashboard,
+    ));
+
     // Finally, start a single shard, and start listening to events.
     //
     // Shards will automatically attempt to reconnect, and will perform
     // exponential backoff until it reconnects.
     if let Err(why) = client.start().await {
-        println!("Client error: {why:?}");
+        error!("Client error: {why:?}");
     }
 }
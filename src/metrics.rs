@@ -0,0 +1,183 @@
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::LazyLock;
+
+use diesel::r2d2::{ConnectionManager, Pool};
+use diesel::SqliteConnection;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use prometheus::{
+    register_histogram_vec, register_int_counter, register_int_counter_vec, register_int_gauge,
+    Encoder, HistogramVec, IntCounter, IntCounterVec, IntGauge, TextEncoder,
+};
+use tracing::{error, info};
+
+pub static LETTERS_SUBMITTED: LazyLock<IntCounter> = LazyLock::new(|| {
+    register_int_counter!(
+        "valentines_letters_submitted_total",
+        "Letters stored after /sendletter"
+    )
+    .unwrap()
+});
+
+pub static LETTERS_REJECTED: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "valentines_letters_rejected_total",
+        "Letters that were not stored, by reason",
+        &["reason"]
+    )
+    .unwrap()
+});
+
+pub static LETTERS_FLAGGED: LazyLock<IntCounter> = LazyLock::new(|| {
+    register_int_counter!(
+        "valentines_letters_flagged_total",
+        "Letters stored but marked for review, by the content filter or as a duplicate"
+    )
+    .unwrap()
+});
+
+pub static LETTERS_DUPLICATE: LazyLock<IntCounter> = LazyLock::new(|| {
+    register_int_counter!(
+        "valentines_letters_duplicate_total",
+        "Letters stored that look a lot like an earlier letter"
+    )
+    .unwrap()
+});
+
+pub static LETTERS_DELETED: LazyLock<IntCounter> = LazyLock::new(|| {
+    register_int_counter!(
+        "valentines_letters_deleted_total",
+        "Letters deleted by moderators"
+    )
+    .unwrap()
+});
+
+pub static PUBLISH_TOTAL: LazyLock<IntGauge> = LazyLock::new(|| {
+    register_int_gauge!(
+        "valentines_publish_letters",
+        "Letters in the current or last /publish run"
+    )
+    .unwrap()
+});
+
+pub static PUBLISH_SENT: LazyLock<IntGauge> = LazyLock::new(|| {
+    register_int_gauge!(
+        "valentines_publish_letters_sent",
+        "Letters sent so far by the current or last /publish run"
+    )
+    .unwrap()
+});
+
+pub static INTERACTION_DURATION: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register_histogram_vec!(
+        "valentines_interaction_duration_seconds",
+        "Time taken to handle an interaction, by kind and command or component",
+        &["kind", "name"]
+    )
+    .unwrap()
+});
+
+pub static GATEWAY_CONNECTED: LazyLock<IntGauge> = LazyLock::new(|| {
+    register_int_gauge!(
+        "valentines_gateway_connected",
+        "Whether the bot is connected to the Discord gateway"
+    )
+    .unwrap()
+});
+
+static DB_CONNECTIONS: LazyLock<IntGauge> = LazyLock::new(|| {
+    register_int_gauge!(
+        "valentines_db_pool_connections",
+        "Connections currently held by the database pool"
+    )
+    .unwrap()
+});
+
+static DB_IDLE_CONNECTIONS: LazyLock<IntGauge> = LazyLock::new(|| {
+    register_int_gauge!(
+        "valentines_db_pool_idle_connections",
+        "Idle connections in the database pool"
+    )
+    .unwrap()
+});
+
+/// Registers every metric, so they are all exported before they first change.
+fn init() {
+    LazyLock::force(&LETTERS_SUBMITTED);
+    LazyLock::force(&LETTERS_REJECTED);
+    LazyLock::force(&LETTERS_FLAGGED);
+    LazyLock::force(&LETTERS_DUPLICATE);
+    LazyLock::force(&LETTERS_DELETED);
+    LazyLock::force(&PUBLISH_TOTAL);
+    LazyLock::force(&PUBLISH_SENT);
+    LazyLock::force(&INTERACTION_DURATION);
+    LazyLock::force(&GATEWAY_CONNECTED);
+    LazyLock::force(&DB_CONNECTIONS);
+    LazyLock::force(&DB_IDLE_CONNECTIONS);
+}
+
+fn text(status: StatusCode, body: impl Into<Body>) -> Response<Body> {
+    let mut response = Response::new(body.into());
+    *response.status_mut() = status;
+    response
+}
+
+async fn handle(
+    request: Request<Body>,
+    db_pool: Pool<ConnectionManager<SqliteConnection>>,
+) -> Result<Response<Body>, Infallible> {
+    if request.method() != Method::GET {
+        return Ok(text(StatusCode::METHOD_NOT_ALLOWED, "Method not allowed"));
+    }
+
+    Ok(match request.uri().path() {
+        "/metrics" => {
+            let state = db_pool.state();
+            DB_CONNECTIONS.set(state.connections.into());
+            DB_IDLE_CONNECTIONS.set(state.idle_connections.into());
+
+            let mut buffer = vec![];
+            match TextEncoder::new().encode(&prometheus::gather(), &mut buffer) {
+                Ok(()) => text(StatusCode::OK, buffer),
+                Err(e) => text(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+            }
+        }
+        "/healthz" => {
+            let database = tokio::task::spawn_blocking(move || db_pool.get().is_ok())
+                .await
+                .unwrap_or(false);
+            let gateway = GATEWAY_CONNECTED.get() == 1;
+
+            if database && gateway {
+                text(StatusCode::OK, "ok")
+            } else {
+                text(
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    format!("database: {database}, gateway: {gateway}"),
+                )
+            }
+        }
+        _ => text(StatusCode::NOT_FOUND, "Not found"),
+    })
+}
+
+/// Serves `/metrics` for Prometheus and `/healthz` for container health checks.
+pub async fn serve(addr: SocketAddr, db_pool: Pool<ConnectionManager<SqliteConnection>>) {
+    init();
+
+    let make_service = make_service_fn(move |_| {
+        let db_pool = db_pool.clone();
+        async move { Ok::<_, Infallible>(service_fn(move |request| handle(request, db_pool.clone()))) }
+    });
+
+    match Server::try_bind(&addr) {
+        Ok(server) => {
+            info!("Serving metrics on http://{addr}/metrics");
+            if let Err(why) = server.serve(make_service).await {
+                error!("Metrics server error: {why}");
+            }
+        }
+        Err(why) => error!("Cannot serve metrics on {addr}: {why}"),
+    }
+}
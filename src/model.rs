@@ -1,26 +1,73 @@
-use crate::schema::{letters, recipients};
+use crate::commands::send::ValentineLetter;
+use crate::encryption::Encrypted;
+use crate::schema::{audit_events, blocked_users, letters, recipients, votes};
+use chrono::NaiveDateTime;
 use diesel::prelude::*;
 
-#[derive(Queryable)]
+/// The sender ID of imported letters that came without one, such as those
+/// collected with a Google Form. It is no Discord user's ID, so these letters
+/// count towards no one's quota and their sender cannot be blocked.
+pub const NO_SENDER_ID: &str = "unknown";
+
+/// A stored letter. Its sender and content are encrypted in the database when
+/// there is a key, and decrypted when it is loaded.
+#[derive(Queryable, Clone)]
 pub struct Letter {
     pub id: i32,
     pub recipient: String,
+    #[diesel(deserialize_as = Encrypted)]
     pub sender: String,
     pub anon: bool,
+    #[diesel(deserialize_as = Encrypted)]
     pub content: String,
     pub message_id: Option<String>,
+    #[diesel(deserialize_as = Encrypted)]
     pub sender_id: String,
+    pub featured: bool,
+    pub reading_order: Option<i32>,
+    /// Unknown for letters from before this was recorded that were never
+    /// logged to the audit channel.
+    pub created_at: Option<NaiveDateTime>,
+    pub updated_at: Option<NaiveDateTime>,
+    /// Where the letter was sent from, with no guild for DMs. Unknown for
+    /// letters from before this was recorded.
+    pub guild_id: Option<String>,
+    pub channel_id: Option<String>,
 }
 
 #[derive(Insertable)]
 #[diesel(table_name = letters)]
 pub struct NewLetter<'a> {
     pub recipient: &'a str,
+    #[diesel(serialize_as = Encrypted)]
     pub sender: &'a str,
     pub anon: bool,
+    #[diesel(serialize_as = Encrypted)]
     pub content: &'a str,
     pub message_id: Option<String>,
+    #[diesel(serialize_as = Encrypted)]
     pub sender_id: &'a str,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub guild_id: Option<&'a str>,
+    pub channel_id: Option<&'a str>,
+}
+
+impl<'a> NewLetter<'a> {
+    pub fn new(letter: &'a ValentineLetter, message_id: Option<String>) -> Self {
+        Self {
+            sender: &letter.sender,
+            recipient: &letter.recipient,
+            anon: letter.anon,
+            content: &letter.letter,
+            message_id,
+            sender_id: &letter.sender_id,
+            created_at: letter.sent_at,
+            updated_at: letter.sent_at,
+            guild_id: letter.guild_id.as_deref(),
+            channel_id: letter.channel_id.as_deref(),
+        }
+    }
 }
 
 #[derive(Queryable, Insertable)]
@@ -28,3 +75,36 @@ pub struct Recipient {
     pub fullname: String,
     pub is_real: bool,
 }
+
+#[derive(Queryable, Insertable)]
+pub struct Vote {
+    pub letter_id: i32,
+    pub moderator_id: String,
+    pub value: i32,
+}
+
+#[derive(Queryable, Insertable)]
+pub struct BlockedUser {
+    pub user_id: String,
+    pub reason: String,
+    pub blocked_by: String,
+}
+
+#[derive(Queryable)]
+pub struct AuditEvent {
+    pub id: i32,
+    pub action: String,
+    pub actor_id: String,
+    pub target: Option<String>,
+    pub details: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = audit_events)]
+pub struct NewAuditEvent {
+    pub action: String,
+    pub actor_id: String,
+    pub target: Option<String>,
+    pub details: Option<String>,
+}
@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+use std::{env, fs};
+
+use serde::Deserialize;
+use serenity::model::guild::Member;
+use serenity::model::id::RoleId;
+use serenity::model::Permissions;
+
+/// Something only some members may do. Every moderation command, button and
+/// modal needs one of these.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModAction {
+    /// Deleting letters from the audit channel.
+    Delete,
+    /// Voting on and featuring letters, and the commands that read them
+    /// before they are published.
+    Review,
+    /// Blocking and unblocking senders.
+    Block,
+    Publish,
+    /// `/add_recipient`.
+    Recipients,
+    /// `/allow_letters`.
+    AllowLetters,
+    Import,
+    Export,
+    /// `/auditlog`.
+    AuditLog,
+}
+
+/// Who may take an action.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Level {
+    /// Members with the event moderator role, or Manage Messages if there is
+    /// no such role. Admins always count as moderators.
+    Moderator,
+    /// Members with Manage Server.
+    Admin,
+}
+
+impl ModAction {
+    /// Who may take the action unless `PERMISSIONS` says otherwise.
+    pub fn default_level(self) -> Level {
+        match self {
+            ModAction::Delete
+            | ModAction::Review
+            | ModAction::Block
+            | ModAction::Publish
+            | ModAction::Export => Level::Moderator,
+            ModAction::Recipients
+            | ModAction::AllowLetters
+            | ModAction::Import
+            | ModAction::AuditLog => Level::Admin,
+        }
+    }
+}
+
+/// Who may take which action.
+#[derive(Debug, Default)]
+pub struct Access {
+    /// The event moderator role, from `MODERATOR_ROLE_ID`.
+    pub moderator_role: Option<RoleId>,
+    /// Levels that differ from the defaults, from the JSON file in
+    /// `PERMISSIONS`.
+    pub levels: HashMap<ModAction, Level>,
+}
+
+impl Access {
+    /// Reads `MODERATOR_ROLE_ID` and the file named by `PERMISSIONS`, both of
+    /// which are optional and may be left empty.
+    pub fn from_env() -> Result<Self, String> {
+        let moderator_role = match env::var("MODERATOR_ROLE_ID")
+            .ok()
+            .filter(|id| !id.is_empty())
+        {
+            Some(id) => {
+                Some(RoleId(id.parse().map_err(|e| {
+                    format!("MODERATOR_ROLE_ID must be a role ID: {e}")
+                })?))
+            }
+            None => None,
+        };
+        let levels = match env::var("PERMISSIONS").ok().filter(|path| !path.is_empty()) {
+            Some(path) => {
+                let levels = fs::read_to_string(&path)
+                    .map_err(|e| format!("Could not read permissions {path}: {e}"))?;
+                serde_json::from_str(&levels)
+                    .map_err(|e| format!("Invalid permissions {path}: {e}"))?
+            }
+            None => HashMap::new(),
+        };
+
+        Ok(Access {
+            moderator_role,
+            levels,
+        })
+    }
+
+    pub fn level(&self, action: ModAction) -> Level {
+        self.levels
+            .get(&action)
+            .copied()
+            .unwrap_or_else(|| action.default_level())
+    }
+
+    /// What members need for Discord to show them the commands that take
+    /// `action`. Members with the event moderator role may have none of the
+    /// usual permissions, so with a role every member sees moderator commands
+    /// and [`Access::check`] decides who may use them.
+    pub fn default_member_permissions(&self, action: ModAction) -> Option<Permissions> {
+        match (self.level(action), self.moderator_role) {
+            (Level::Admin, _) => Some(Permissions::MANAGE_GUILD),
+            (Level::Moderator, Some(_)) => None,
+            (Level::Moderator, None) => Some(Permissions::MANAGE_MESSAGES),
+        }
+    }
+
+    /// Checks that `member` may take `action`, and says what they are missing
+    /// if they may not. Outside of a guild there is no member, and no one may.
+    pub fn check(&self, member: Option<&Member>, action: ModAction) -> Result<(), String> {
+        let Some(member) = member else {
+            return Err("This can only be done in the server.".to_owned());
+        };
+        let permissions = member.permissions.unwrap_or_default();
+        let is_admin = permissions.manage_guild();
+
+        let (allowed, required) = match (self.level(action), self.moderator_role) {
+            (Level::Admin, _) => (is_admin, "Manage Server permission"),
+            (Level::Moderator, Some(role)) => (
+                is_admin || member.roles.contains(&role),
+                "event moderator role",
+            ),
+            (Level::Moderator, None) => (
+                is_admin || permissions.manage_messages(),
+                "Manage Messages permission",
+            ),
+        };
+
+        if allowed {
+            Ok(())
+        } else {
+            Err(format!(
+                "You aren't allowed to do this. ({required} required)"
+            ))
+        }
+    }
+}
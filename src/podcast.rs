@@ -0,0 +1,141 @@
+use std::fmt::Write;
+
+use chrono::Utc;
+use diesel::dsl::max;
+use diesel::prelude::*;
+
+use crate::model::Letter;
+
+/// A relaxed reading pace, used to estimate how long a letter takes on air.
+const WORDS_PER_MINUTE: usize = 150;
+
+/// Featured letters in the order they will be read.
+pub fn load_featured(conn: &mut SqliteConnection) -> QueryResult<Vec<Letter>> {
+    use crate::schema::letters::dsl::{featured, id, letters, reading_order};
+
+    letters
+        .filter(featured.eq(true))
+        .order((reading_order.asc(), id.asc()))
+        .load(conn)
+}
+
+/// Features the letter logged in the given audit message, or removes it from
+/// the podcast if it was already featured. Newly featured letters are read last.
+pub fn toggle_featured(conn: &mut SqliteConnection, audit_message: &str) -> QueryResult<Letter> {
+    use crate::schema::letters::dsl::{featured, letters, message_id, reading_order, updated_at};
+
+    conn.transaction(|conn| {
+        let letter: Letter = letters.filter(message_id.eq(audit_message)).first(conn)?;
+
+        let position = if letter.featured {
+            None
+        } else {
+            let last: Option<i32> = letters.select(max(reading_order)).first(conn)?;
+            Some(last.unwrap_or(0) + 1)
+        };
+
+        diesel::update(letters.find(letter.id))
+            .set((
+                featured.eq(!letter.featured),
+                reading_order.eq(position),
+                updated_at.eq(Utc::now().naive_utc()),
+            ))
+            .execute(conn)?;
+
+        let order = load_featured(conn)?;
+        renumber(conn, &order)?;
+
+        letters.find(letter.id).first(conn)
+    })
+}
+
+/// Moves a featured letter to a 1-based position in the reading order and
+/// returns the new order.
+pub fn move_letter(
+    conn: &mut SqliteConnection,
+    letter_id: i32,
+    position: usize,
+) -> QueryResult<Vec<Letter>> {
+    conn.transaction(|conn| {
+        let mut order = load_featured(conn)?;
+        let index = order
+            .iter()
+            .position(|letter| letter.id == letter_id)
+            .ok_or(diesel::result::Error::NotFound)?;
+
+        let letter = order.remove(index);
+        order.insert(position.saturating_sub(1).min(order.len()), letter);
+        renumber(conn, &order)?;
+
+        load_featured(conn)
+    })
+}
+
+fn renumber(conn: &mut SqliteConnection, order: &[Letter]) -> QueryResult<()> {
+    use crate::schema::letters::dsl::{letters, reading_order, updated_at};
+
+    for (position, letter) in (1..).zip(order) {
+        if letter.reading_order != Some(position) {
+            diesel::update(letters.find(letter.id))
+                .set((
+                    reading_order.eq(position),
+                    updated_at.eq(Utc::now().naive_utc()),
+                ))
+                .execute(conn)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn word_count(content: &str) -> usize {
+    content.split_whitespace().count()
+}
+
+pub fn read_time(words: usize) -> String {
+    let seconds = (words * 60).div_ceil(WORDS_PER_MINUTE);
+    match (seconds / 60, seconds % 60) {
+        (0, seconds) => format!("{seconds} s"),
+        (minutes, 0) => format!("{minutes} min"),
+        (minutes, seconds) => format!("{minutes} min {seconds} s"),
+    }
+}
+
+/// Renders the featured letters as a Markdown reading script. Anonymous
+/// letters never show their sender since the script is read on air.
+pub fn script(letters: &[Letter]) -> String {
+    let total_words: usize = letters
+        .iter()
+        .map(|letter| word_count(&letter.content))
+        .sum();
+
+    let mut out = String::from("# Podcast of the Elite: Valentine's letters\n\n");
+    let _ = writeln!(
+        out,
+        "{} letters, {total_words} words, about {} of reading.",
+        letters.len(),
+        read_time(total_words)
+    );
+
+    let mut current_recipient = None;
+    for (number, letter) in (1..).zip(letters) {
+        if current_recipient != Some(&letter.recipient) {
+            let _ = write!(out, "\n## To {}\n", letter.recipient);
+            current_recipient = Some(&letter.recipient);
+        }
+
+        let words = word_count(&letter.content);
+        let from = if letter.anon {
+            "Anonymous"
+        } else {
+            letter.sender.as_str()
+        };
+        let _ = write!(
+            out,
+            "\n### {number}. From {from} (letter #{})\n\n*{words} words, about {}*\n\n{}\n",
+            letter.id,
+            read_time(words),
+            letter.content
+        );
+    }
+    out
+}
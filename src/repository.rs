@@ -0,0 +1,324 @@
+use std::fmt;
+use std::sync::{Arc, RwLock};
+
+use diesel::prelude::*;
+use diesel::r2d2::{ConnectionManager, Pool, PoolError};
+use serenity::async_trait;
+use tokio::task::JoinError;
+
+use crate::commands::send::ValentineLetter;
+use crate::duplicates::{self, Duplicate};
+use crate::encryption::TryEncrypted;
+use crate::model::{Letter, NewAuditEvent, NewLetter, Recipient, NO_SENDER_ID};
+use crate::schema::letters::all_columns;
+use crate::{audit, blocklist};
+
+pub type DbPool = Pool<ConnectionManager<SqliteConnection>>;
+
+/// Discord shows at most 25 autocomplete suggestions.
+const MAX_SUGGESTIONS: usize = 25;
+
+#[derive(Debug)]
+pub enum RepositoryError {
+    Pool(PoolError),
+    Query(diesel::result::Error),
+    /// The blocking task running the query panicked.
+    Task(JoinError),
+}
+
+impl fmt::Display for RepositoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RepositoryError::Pool(e) => write!(f, "no database connection available: {e}"),
+            RepositoryError::Query(e) => write!(f, "query failed: {e}"),
+            RepositoryError::Task(e) => write!(f, "database task failed: {e}"),
+        }
+    }
+}
+
+impl From<PoolError> for RepositoryError {
+    fn from(e: PoolError) -> Self {
+        RepositoryError::Pool(e)
+    }
+}
+
+impl From<diesel::result::Error> for RepositoryError {
+    fn from(e: diesel::result::Error) -> Self {
+        RepositoryError::Query(e)
+    }
+}
+
+/// How many letters a single sender may send.
+pub const LETTERS_PER_SENDER: i64 = 2;
+
+/// The letter and recipient queries made by interaction handlers.
+#[async_trait]
+pub trait LetterRepository: Send + Sync {
+    async fn letters_sent_by(&self, sender: String) -> Result<i64, RepositoryError>;
+
+    /// Whether `sender` has not used up their quota yet.
+    async fn can_send_letter(&self, sender: String) -> Result<bool, RepositoryError> {
+        Ok(self.letters_sent_by(sender).await? < LETTERS_PER_SENDER)
+    }
+
+    /// Stores a letter and returns its ID.
+    async fn add_letter(
+        &self,
+        letter: ValentineLetter,
+        message_id: Option<String>,
+    ) -> Result<i32, RepositoryError>;
+
+    /// Deletes the letter logged in the given audit message, if it still exists.
+    async fn delete_letter(&self, message_id: String) -> Result<Option<Letter>, RepositoryError>;
+
+    /// The stored letter most like `content`, if it is alike enough to be a
+    /// duplicate.
+    async fn find_duplicate(
+        &self,
+        sender_id: String,
+        content: String,
+    ) -> Result<Option<Duplicate>, RepositoryError>;
+
+    /// Whether the user with this ID was blocked from sending letters.
+    async fn is_blocked(&self, user_id: String) -> Result<bool, RepositoryError>;
+
+    /// Blocks the sender of the letter logged in the given audit message and
+    /// returns their ID and name, if the letter still exists and has a sender
+    /// ID. Fails if the sender cannot be decrypted.
+    async fn block_sender(
+        &self,
+        message_id: String,
+        reason: String,
+        moderator: String,
+    ) -> Result<Option<(String, String)>, RepositoryError>;
+
+    async fn add_recipient(&self, recipient: Recipient) -> Result<(), RepositoryError>;
+
+    /// Replaces every recipient with placeholder recipients of these names.
+    async fn seed_recipients(&self, names: Vec<String>) -> Result<(), RepositoryError>;
+
+    /// Reloads the recipients used for autocomplete, for when they were
+    /// changed without going through the repository.
+    async fn reload_recipients(&self) -> Result<(), RepositoryError>;
+
+    /// Adds a moderator action to the audit log.
+    async fn record_event(&self, event: NewAuditEvent) -> Result<(), RepositoryError>;
+
+    /// Recipients whose name contains `query`, ignoring case.
+    fn search_recipients(&self, query: &str) -> Vec<String>;
+}
+
+/// Database access for interaction handlers.
+///
+/// Diesel blocks, so every query runs on tokio's blocking thread pool rather
+/// than on the worker threads that handle gateway events.
+#[derive(Clone)]
+pub struct Repository {
+    pool: DbPool,
+    /// Recipient names for autocomplete, so typing a name does not query the
+    /// database on every keystroke.
+    recipients: Arc<RwLock<Vec<String>>>,
+}
+
+impl Repository {
+    pub fn new(pool: DbPool) -> Self {
+        Self {
+            pool,
+            recipients: Arc::default(),
+        }
+    }
+
+    pub fn pool(&self) -> &DbPool {
+        &self.pool
+    }
+
+    /// Runs `f` with a pooled connection on the blocking thread pool.
+    pub async fn run<T, F>(&self, f: F) -> Result<T, RepositoryError>
+    where
+        F: FnOnce(&mut SqliteConnection) -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let pool = self.pool.clone();
+        tokio::task::spawn_blocking(move || Ok(f(&mut *pool.get()?)))
+            .await
+            .map_err(RepositoryError::Task)?
+    }
+
+    /// Like [`Repository::run`], for functions that only fail on a query.
+    pub async fn query<T, F>(&self, f: F) -> Result<T, RepositoryError>
+    where
+        F: FnOnce(&mut SqliteConnection) -> QueryResult<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        Ok(self.run(f).await??)
+    }
+}
+
+#[async_trait]
+impl LetterRepository for Repository {
+    async fn letters_sent_by(&self, sender: String) -> Result<i64, RepositoryError> {
+        self.query(move |conn| letters_sent_by(conn, &sender)).await
+    }
+
+    async fn add_letter(
+        &self,
+        letter: ValentineLetter,
+        message_id: Option<String>,
+    ) -> Result<i32, RepositoryError> {
+        self.query(move |conn| add_letter(conn, &letter, message_id))
+            .await
+    }
+
+    async fn delete_letter(&self, message_id: String) -> Result<Option<Letter>, RepositoryError> {
+        self.query(move |conn| delete_letter(conn, &message_id))
+            .await
+    }
+
+    async fn find_duplicate(
+        &self,
+        sender_id: String,
+        content: String,
+    ) -> Result<Option<Duplicate>, RepositoryError> {
+        self.query(move |conn| duplicates::find_duplicate(conn, &sender_id, &content))
+            .await
+    }
+
+    async fn is_blocked(&self, user_id: String) -> Result<bool, RepositoryError> {
+        self.query(move |conn| blocklist::is_blocked(conn, &user_id))
+            .await
+    }
+
+    async fn block_sender(
+        &self,
+        message_id: String,
+        reason: String,
+        moderator: String,
+    ) -> Result<Option<(String, String)>, RepositoryError> {
+        self.query(move |conn| blocklist::block_sender(conn, &message_id, &reason, &moderator))
+            .await
+    }
+
+    async fn add_recipient(&self, recipient: Recipient) -> Result<(), RepositoryError> {
+        let fullname = recipient.fullname.clone();
+        self.query(move |conn| add_recipient(conn, &recipient))
+            .await?;
+        self.recipients.write().unwrap().push(fullname);
+        Ok(())
+    }
+
+    async fn seed_recipients(&self, names: Vec<String>) -> Result<(), RepositoryError> {
+        self.query(move |conn| seed_recipients(conn, &names))
+            .await?;
+        self.reload_recipients().await
+    }
+
+    async fn reload_recipients(&self) -> Result<(), RepositoryError> {
+        let names = self.query(recipient_names).await?;
+        *self.recipients.write().unwrap() = names;
+        Ok(())
+    }
+
+    async fn record_event(&self, event: NewAuditEvent) -> Result<(), RepositoryError> {
+        self.query(move |conn| audit::record(conn, &event)).await
+    }
+
+    fn search_recipients(&self, query: &str) -> Vec<String> {
+        let query = query.to_lowercase();
+        self.recipients
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|name| name.to_lowercase().contains(&query))
+            .take(MAX_SUGGESTIONS)
+            .cloned()
+            .collect()
+    }
+}
+
+/// Parses the `RECIPIENTS` variable: names separated by `:`, with `_` for
+/// spaces.
+pub fn parse_recipients(list: &str) -> Vec<String> {
+    list.split(':').map(|name| name.replace('_', " ")).collect()
+}
+
+/// Senders may be encrypted, with a different nonce every time, so they are
+/// compared once loaded rather than in the query.
+pub fn letters_sent_by(conn: &mut SqliteConnection, sender_name: &str) -> QueryResult<i64> {
+    use crate::schema::letters::dsl::{letters, sender, sender_id};
+
+    let senders: Vec<(TryEncrypted, TryEncrypted)> =
+        letters.select((sender, sender_id)).load(conn)?;
+    Ok(senders
+        .iter()
+        .filter(|(TryEncrypted(name), TryEncrypted(id))| name == sender_name && id != NO_SENDER_ID)
+        .count() as i64)
+}
+
+pub fn add_letter(
+    conn: &mut SqliteConnection,
+    letter: &ValentineLetter,
+    message_id: Option<String>,
+) -> QueryResult<i32> {
+    use crate::schema::letters::dsl::{id, letters};
+
+    diesel::insert_into(letters)
+        .values(NewLetter::new(letter, message_id))
+        .returning(id)
+        .get_result(conn)
+}
+
+pub fn delete_letter(
+    conn: &mut SqliteConnection,
+    audit_message: &str,
+) -> QueryResult<Option<Letter>> {
+    use crate::schema::letters::dsl::{id, letters, message_id};
+    use crate::schema::votes::dsl::{letter_id, votes};
+
+    conn.transaction(|conn| {
+        let logged = letters.filter(message_id.eq(audit_message));
+        let Some(deleted_id) = logged.select(id).first::<i32>(conn).optional()? else {
+            return Ok(None);
+        };
+        // votes reference the letter, so they have to go first
+        diesel::delete(votes.filter(letter_id.eq(deleted_id))).execute(conn)?;
+        diesel::delete(logged)
+            .returning(all_columns)
+            .get_result(conn)
+            .map(Some)
+    })
+}
+
+pub fn add_recipient(conn: &mut SqliteConnection, recipient: &Recipient) -> QueryResult<()> {
+    use crate::schema::recipients::dsl::recipients;
+
+    diesel::insert_into(recipients)
+        .values(recipient)
+        .execute(conn)
+        .map(|_| ())
+}
+
+pub fn seed_recipients(conn: &mut SqliteConnection, names: &[String]) -> QueryResult<()> {
+    use crate::schema::recipients::dsl::recipients;
+
+    let seeded = names
+        .iter()
+        .map(|name| Recipient {
+            fullname: name.clone(),
+            is_real: false,
+        })
+        .collect::<Vec<_>>();
+
+    conn.transaction(|conn| {
+        diesel::delete(recipients).execute(conn)?;
+        diesel::insert_into(recipients)
+            .values(&seeded)
+            .execute(conn)
+            .map(|_| ())
+    })
+}
+
+pub fn recipient_names(conn: &mut SqliteConnection) -> QueryResult<Vec<String>> {
+    use crate::schema::recipients::dsl::{fullname, recipients};
+
+    recipients.select(fullname).load(conn)
+}
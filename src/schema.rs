@@ -1,5 +1,32 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    audit_events (id) {
+        id -> Integer,
+        action -> Text,
+        actor_id -> Text,
+        target -> Nullable<Text>,
+        details -> Nullable<Text>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    blocked_users (user_id) {
+        user_id -> Text,
+        reason -> Text,
+        blocked_by -> Text,
+    }
+}
+
+diesel::table! {
+    dashboard (id) {
+        id -> Integer,
+        channel_id -> Text,
+        message_id -> Text,
+    }
+}
+
 diesel::table! {
     letters (id) {
         id -> Integer,
@@ -9,6 +36,12 @@ diesel::table! {
         content -> Text,
         message_id -> Nullable<Text>,
         sender_id -> Text,
+        featured -> Bool,
+        reading_order -> Nullable<Integer>,
+        created_at -> Nullable<Timestamp>,
+        updated_at -> Nullable<Timestamp>,
+        guild_id -> Nullable<Text>,
+        channel_id -> Nullable<Text>,
     }
 }
 
@@ -19,7 +52,21 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    votes (letter_id, moderator_id) {
+        letter_id -> Integer,
+        moderator_id -> Text,
+        value -> Integer,
+    }
+}
+
+diesel::joinable!(votes -> letters (letter_id));
+
 diesel::allow_tables_to_appear_in_same_query!(
+    audit_events,
+    blocked_users,
+    dashboard,
     letters,
     recipients,
+    votes,
 );
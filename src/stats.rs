@@ -0,0 +1,112 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use chrono::NaiveDate;
+use diesel::prelude::*;
+
+use crate::model::{Letter, Recipient};
+
+pub struct Stats {
+    pub letters: usize,
+    pub anonymous: usize,
+    pub unique_senders: usize,
+    /// Letters per recipient, most letters first.
+    pub per_recipient: Vec<(String, usize)>,
+    pub to_real: usize,
+    pub to_fictional: usize,
+    /// Letters to names that are not in the recipients list.
+    pub to_unlisted: usize,
+    /// Only letters with a known submission time are counted.
+    pub per_day: Vec<(NaiveDate, usize)>,
+}
+
+impl Stats {
+    pub fn anonymous_percentage(&self) -> f64 {
+        if self.letters == 0 {
+            0.0
+        } else {
+            self.anonymous as f64 * 100.0 / self.letters as f64
+        }
+    }
+}
+
+pub fn collect(conn: &mut SqliteConnection) -> QueryResult<Stats> {
+    use crate::schema::letters::dsl::letters;
+    use crate::schema::recipients::dsl::recipients;
+
+    let all_letters = letters.load::<Letter>(conn)?;
+    let is_real: HashMap<String, bool> = recipients
+        .load::<Recipient>(conn)?
+        .into_iter()
+        .map(|recipient| (recipient.fullname, recipient.is_real))
+        .collect();
+
+    let mut per_recipient: HashMap<&str, usize> = HashMap::new();
+    let mut per_day: BTreeMap<NaiveDate, usize> = BTreeMap::new();
+    let mut senders = HashSet::new();
+    let mut stats = Stats {
+        letters: all_letters.len(),
+        anonymous: 0,
+        unique_senders: 0,
+        per_recipient: vec![],
+        to_real: 0,
+        to_fictional: 0,
+        to_unlisted: 0,
+        per_day: vec![],
+    };
+
+    for letter in &all_letters {
+        *per_recipient.entry(&letter.recipient).or_default() += 1;
+        if let Some(created_at) = letter.created_at {
+            *per_day.entry(created_at.date()).or_default() += 1;
+        }
+        senders.insert(&letter.sender_id);
+
+        if letter.anon {
+            stats.anonymous += 1;
+        }
+        match is_real.get(&letter.recipient) {
+            Some(true) => stats.to_real += 1,
+            Some(false) => stats.to_fictional += 1,
+            None => stats.to_unlisted += 1,
+        }
+    }
+
+    stats.unique_senders = senders.len();
+    stats.per_recipient = per_recipient
+        .into_iter()
+        .map(|(recipient, count)| (recipient.to_owned(), count))
+        .collect();
+    stats
+        .per_recipient
+        .sort_by(|(a_name, a_count), (b_name, b_count)| {
+            b_count.cmp(a_count).then_with(|| a_name.cmp(b_name))
+        });
+    stats.per_day = per_day.into_iter().collect();
+
+    Ok(stats)
+}
+
+/// Where the pinned statistics message is, as the IDs of its channel and of
+/// the message, if one was pinned.
+pub fn load_dashboard(conn: &mut SqliteConnection) -> QueryResult<Option<(String, String)>> {
+    use crate::schema::dashboard::dsl::{channel_id, dashboard, message_id};
+
+    dashboard
+        .select((channel_id, message_id))
+        .first(conn)
+        .optional()
+}
+
+/// Remembers the pinned statistics message, replacing the previous one.
+pub fn save_dashboard(
+    conn: &mut SqliteConnection,
+    channel: &str,
+    message: &str,
+) -> QueryResult<()> {
+    use crate::schema::dashboard::dsl::{channel_id, dashboard, id, message_id};
+
+    diesel::replace_into(dashboard)
+        .values((id.eq(1), channel_id.eq(channel), message_id.eq(message)))
+        .execute(conn)
+        .map(|_| ())
+}
@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+
+use diesel::prelude::*;
+
+use crate::model::{Letter, Vote};
+
+/// The sum of all votes on a letter, and how many moderators voted.
+#[derive(Clone, Copy, Default)]
+pub struct Score {
+    pub total: i32,
+    pub votes: usize,
+}
+
+impl std::fmt::Display for Score {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:+} ({} vote{})",
+            self.total,
+            self.votes,
+            if self.votes == 1 { "" } else { "s" }
+        )
+    }
+}
+
+/// Votes on the letter logged in the given audit message. Voting the same way
+/// twice takes the vote back.
+pub fn cast_vote(
+    conn: &mut SqliteConnection,
+    audit_message: &str,
+    moderator: &str,
+    vote: i32,
+) -> QueryResult<(Letter, Score)> {
+    use crate::schema::letters::dsl::{letters, message_id};
+    use crate::schema::votes::dsl::{letter_id, moderator_id, value, votes};
+
+    conn.transaction(|conn| {
+        let letter: Letter = letters.filter(message_id.eq(audit_message)).first(conn)?;
+        let own_vote = votes
+            .filter(letter_id.eq(letter.id))
+            .filter(moderator_id.eq(moderator));
+
+        let previous: Option<i32> = own_vote.select(value).first(conn).optional()?;
+        if previous == Some(vote) {
+            diesel::delete(own_vote).execute(conn)?;
+        } else {
+            diesel::insert_into(votes)
+                .values(Vote {
+                    letter_id: letter.id,
+                    moderator_id: moderator.to_owned(),
+                    value: vote,
+                })
+                .on_conflict((letter_id, moderator_id))
+                .do_update()
+                .set(value.eq(vote))
+                .execute(conn)?;
+        }
+
+        let values: Vec<i32> = votes
+            .filter(letter_id.eq(letter.id))
+            .select(value)
+            .load(conn)?;
+        let score = Score {
+            total: values.iter().sum(),
+            votes: values.len(),
+        };
+
+        Ok((letter, score))
+    })
+}
+
+/// All letters ranked by score, with older letters first on a tie.
+pub fn shortlist(conn: &mut SqliteConnection) -> QueryResult<Vec<(Letter, Score)>> {
+    use crate::schema::letters::dsl::{created_at, id, letters};
+    use crate::schema::votes::dsl::votes;
+
+    let mut scores: HashMap<i32, Score> = HashMap::new();
+    for vote in votes.load::<Vote>(conn)? {
+        let score = scores.entry(vote.letter_id).or_default();
+        score.total += vote.value;
+        score.votes += 1;
+    }
+
+    let mut ranked: Vec<(Letter, Score)> = letters
+        .order((created_at.asc(), id.asc()))
+        .load::<Letter>(conn)?
+        .into_iter()
+        .map(|letter| {
+            let score = scores.get(&letter.id).copied().unwrap_or_default();
+            (letter, score)
+        })
+        .collect();
+    // the sort is stable, so letters with the same score stay in submission order
+    ranked.sort_by_key(|(_, score)| -score.total);
+
+    Ok(ranked)
+}
@@ -0,0 +1,79 @@
+use std::fs;
+use std::path::PathBuf;
+
+use cotevalentines::archive::write_archive;
+use cotevalentines::commands::send::ValentineLetter;
+use cotevalentines::export::load_letters;
+use cotevalentines::repository::LetterRepository;
+
+mod common;
+
+use common::{letter, repository, LETTER};
+
+/// An empty directory for one test, removed again when it is dropped.
+struct Scratch(PathBuf);
+
+impl Scratch {
+    fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("archive-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        Scratch(dir)
+    }
+
+    fn read(&self, path: &str) -> String {
+        fs::read_to_string(self.0.join(path)).unwrap()
+    }
+}
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+#[tokio::test]
+async fn the_archive_has_an_index_and_a_page_per_letter() {
+    let db = repository();
+    for sent in [
+        letter("Ayanokouji", "Karuizawa Kei"),
+        ValentineLetter {
+            anon: true,
+            ..letter("Kushida", "Horikita Suzune")
+        },
+        ValentineLetter {
+            letter: format!("{LETTER} <script>alert('<3')</script>"),
+            ..letter("Ryuuen", "Karuizawa Kei")
+        },
+    ] {
+        db.add_letter(sent, None).await.unwrap();
+    }
+    let letters = db.query(load_letters).await.unwrap();
+    let archive = Scratch::new("index");
+
+    write_archive(&letters, &archive.0).unwrap();
+
+    let index = archive.read("index.html");
+    assert!(index.contains("<link rel=\"stylesheet\" href=\"style.css\">"));
+    assert!(index.contains("<li><a href=\"#horikita-suzune\">Horikita Suzune</a> (1 letter)</li>"));
+    assert!(index.contains("<li><a href=\"#karuizawa-kei\">Karuizawa Kei</a> (2 letters)</li>"));
+    assert!(index.contains("<li><a href=\"letters/2.html\">From Anonymous</a>: "));
+    assert!(index.contains("<li><a href=\"letters/1.html\">From Ayanokouji</a>: "));
+    assert!(!archive.read("style.css").is_empty());
+
+    let signed = archive.read("letters/1.html");
+    assert!(signed.contains(&format!(
+        "<p class=\"letter\">{}</p>",
+        LETTER.replace('\'', "&#39;")
+    )));
+    assert!(signed.contains("<p>From Ayanokouji</p>"));
+    assert!(signed.contains("<a href=\"../index.html#karuizawa-kei\">"));
+
+    let anonymous = archive.read("letters/2.html");
+    assert!(anonymous.contains("<p>From Anonymous</p>"));
+    assert!(!anonymous.contains("Kushida"));
+    assert!(!index.contains("Kushida"));
+
+    let escaped = archive.read("letters/3.html");
+    assert!(!escaped.contains("<script>"));
+    assert!(escaped.contains("&lt;script&gt;alert(&#39;&lt;3&#39;)&lt;/script&gt;"));
+}
@@ -0,0 +1,84 @@
+use cotevalentines::audit::{self, events, Action, EventFilter};
+use cotevalentines::repository::LetterRepository;
+
+mod common;
+
+use common::repository;
+
+#[tokio::test]
+async fn events_are_filtered_and_newest_first() {
+    let db = repository();
+    let recorded = [
+        Action::Vote.by(2).on(audit::letter(1)).details("+1"),
+        Action::Delete.by(2).on(audit::letter(1)),
+        Action::Block.by(3).on(audit::user(42)).details("Spam"),
+        Action::AllowLetters.by(3).details("not allowed"),
+    ];
+    for event in recorded {
+        db.record_event(event).await.unwrap();
+    }
+    let find = |filter: EventFilter, limit| {
+        let db = db.clone();
+        async move {
+            db.query(move |conn| events(conn, &filter, limit))
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|event| event.action)
+                .collect::<Vec<_>>()
+        }
+    };
+
+    assert_eq!(
+        find(EventFilter::default(), 10).await,
+        ["allow_letters", "block", "delete", "vote"]
+    );
+    assert_eq!(find(EventFilter::default(), 1).await, ["allow_letters"]);
+    let by_action = EventFilter {
+        action: Some(Action::Delete),
+        ..Default::default()
+    };
+    assert_eq!(find(by_action, 10).await, ["delete"]);
+    let by_moderator = EventFilter {
+        actor_id: Some("2".to_owned()),
+        ..Default::default()
+    };
+    assert_eq!(find(by_moderator, 10).await, ["delete", "vote"]);
+    let on_user = EventFilter {
+        target: Some(audit::user(42)),
+        ..Default::default()
+    };
+    assert_eq!(find(on_user, 10).await, ["block"]);
+}
+
+#[tokio::test]
+async fn events_read_as_one_line() {
+    let db = repository();
+    db.record_event(
+        Action::AddRecipient
+            .by(2)
+            .on(audit::recipient("@everyone"))
+            .details("fictional"),
+    )
+    .await
+    .unwrap();
+
+    let found = db
+        .query(|conn| events(conn, &EventFilter::default(), 1))
+        .await
+        .unwrap();
+    let line = found[0].to_string();
+
+    assert!(
+        line.ends_with("**add_recipient** by <@2> on recipient @\u{200B}everyone: fictional"),
+        "{line}"
+    );
+}
+
+#[test]
+fn actions_are_found_by_name() {
+    for action in Action::ALL {
+        assert_eq!(Action::from_name(action.as_str()), Some(action));
+    }
+    assert_eq!(Action::from_name("restore"), None);
+}
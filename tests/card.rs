@@ -0,0 +1,56 @@
+use cotevalentines::card::render_card;
+use cotevalentines::commands::send::ValentineLetter;
+use cotevalentines::model::Letter;
+use cotevalentines::repository::LetterRepository;
+use cotevalentines::schema::letters::dsl::letters;
+use diesel::prelude::*;
+use tiny_skia::Pixmap;
+
+mod common;
+
+use common::{letter, repository, LETTER};
+
+/// Stores `sent` and loads it back the way commands get letters.
+async fn stored(sent: ValentineLetter) -> Letter {
+    let db = repository();
+    let id = db.add_letter(sent, None).await.unwrap();
+    db.query(move |conn| letters.find(id).first(conn))
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn cards_grow_with_their_letter() {
+    let short = stored(letter("Ayanokouji", "Karuizawa Kei")).await;
+    let long = stored(ValentineLetter {
+        letter: LETTER.repeat(45),
+        ..letter("Ayanokouji", "Karuizawa Kei")
+    })
+    .await;
+
+    let short = Pixmap::decode_png(&render_card(&short).unwrap()).unwrap();
+    let long = Pixmap::decode_png(&render_card(&long).unwrap()).unwrap();
+
+    assert_eq!(short.width(), 1080);
+    assert_eq!(long.width(), 1080);
+    assert!(long.height() > short.height() * 5);
+}
+
+#[tokio::test]
+async fn anonymous_cards_are_not_signed_by_their_sender() {
+    let signed = stored(letter("Ayanokouji", "Karuizawa Kei")).await;
+    let anonymous = stored(ValentineLetter {
+        anon: true,
+        ..letter("Ayanokouji", "Karuizawa Kei")
+    })
+    .await;
+    let by_someone_else = stored(ValentineLetter {
+        anon: true,
+        ..letter("Ryuuen", "Karuizawa Kei")
+    })
+    .await;
+
+    let anonymous = render_card(&anonymous).unwrap();
+    assert_ne!(render_card(&signed).unwrap(), anonymous);
+    assert_eq!(render_card(&by_someone_else).unwrap(), anonymous);
+}
@@ -0,0 +1,45 @@
+use chrono::NaiveDate;
+use cotevalentines::chart::{bar_chart, timeline};
+use tiny_skia::Pixmap;
+
+fn rows(count: usize) -> Vec<(String, usize)> {
+    (0..count)
+        .map(|i| (format!("Recipient {i}"), count - i))
+        .collect()
+}
+
+fn size(png: &[u8]) -> (u32, u32) {
+    let pixmap = Pixmap::decode_png(png).unwrap();
+    (pixmap.width(), pixmap.height())
+}
+
+#[test]
+fn bar_charts_have_a_row_per_recipient() {
+    let five = size(&bar_chart("Letters per recipient", &rows(5)).unwrap());
+    let six = size(&bar_chart("Letters per recipient", &rows(6)).unwrap());
+
+    assert_eq!(five.0, 1000);
+    assert_eq!(six.1 - five.1, 40);
+}
+
+#[test]
+fn recipients_past_the_first_ones_share_a_bar() {
+    let most = bar_chart("Letters per recipient", &rows(25)).unwrap();
+    let all = bar_chart("Letters per recipient", &rows(300)).unwrap();
+
+    assert_eq!(size(&all), size(&most));
+    assert_ne!(all, most);
+}
+
+#[test]
+fn timelines_keep_their_size_however_many_days_they_cover() {
+    let day = |d| NaiveDate::from_ymd_opt(2023, 2, d).unwrap();
+
+    let week = timeline("Submissions per day", &[(day(7), 3), (day(14), 12)]).unwrap();
+    let two_days = timeline("Submissions per day", &[(day(13), 3), (day(14), 12)]).unwrap();
+    let empty = timeline("Submissions per day", &[]).unwrap();
+
+    assert_eq!(size(&week), (1000, 500));
+    assert_ne!(week, two_days);
+    assert_eq!(size(&empty), (1000, 500));
+}
@@ -0,0 +1,371 @@
+//! Helpers shared by the integration tests. Not every test uses all of them.
+#![allow(dead_code)]
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use chrono::NaiveDate;
+use cotevalentines::commands::send::ValentineLetter;
+use cotevalentines::discord::{Discord, Typing};
+use cotevalentines::repository::{LetterRepository, Repository};
+use cotevalentines::run_migrations;
+use diesel::r2d2::{ConnectionManager, Pool};
+use diesel::SqliteConnection;
+use serde_json::json;
+use serenity::{
+    async_trait,
+    builder::{CreateInteractionResponse, CreateMessage, EditInteractionResponse, EditMessage},
+    json::{hashmap_to_json_map, Value},
+    model::guild::Member,
+    model::id::{ChannelId, GuildId, InteractionId, MessageId, UserId},
+    model::prelude::{
+        interaction::{
+            application_command::ApplicationCommandInteraction,
+            message_component::MessageComponentInteraction, modal::ModalSubmitInteraction,
+        },
+        AttachmentType,
+    },
+};
+
+/// A letter long enough to be sent.
+pub const LETTER: &str = concat!(
+    "I have been meaning to tell you this for a while now, but every time I try ",
+    "the words simply will not come out. Happy Valentine's day!",
+);
+
+/// A letter from `sender`, whose ID is `"{sender}#id"`, sent from the guild
+/// on 2023-02-14 at noon.
+pub fn letter(sender: &str, recipient: &str) -> ValentineLetter {
+    ValentineLetter {
+        sender: sender.to_owned(),
+        sender_id: format!("{sender}#id"),
+        recipient: recipient.to_owned(),
+        letter: LETTER.to_owned(),
+        anon: false,
+        sent_at: NaiveDate::from_ymd_opt(2023, 2, 14)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap(),
+        guild_id: Some(GUILD.to_string()),
+        channel_id: Some("301".to_owned()),
+    }
+}
+
+/// A repository with `letters` stored in order, logged in audit messages 100,
+/// 101 and so on.
+pub async fn with_letters(letters: impl IntoIterator<Item = ValentineLetter>) -> Repository {
+    let db = repository();
+    for (message, sent) in (100..).zip(letters) {
+        db.add_letter(sent, Some(message.to_string()))
+            .await
+            .unwrap();
+    }
+    db
+}
+
+/// A repository on a fresh in-memory database with every migration applied.
+///
+/// Each connection to `:memory:` opens its own database, so the pool only
+/// ever holds one connection.
+pub fn repository() -> Repository {
+    let pool = Pool::builder()
+        .max_size(1)
+        .build(ConnectionManager::<SqliteConnection>::new(":memory:"))
+        .expect("Could not build connection pool");
+    run_migrations(&mut *pool.get().unwrap()).expect("Could not run migrations");
+    Repository::new(pool)
+}
+
+/// A request the bot made to Discord, with builders turned into the JSON that
+/// would have been sent.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Call {
+    Respond {
+        interaction: InteractionId,
+        response: Value,
+    },
+    EditResponse {
+        token: String,
+        edit: Value,
+    },
+    SendMessage {
+        channel: ChannelId,
+        message: Value,
+        /// Names of the attached files.
+        files: Vec<String>,
+    },
+    EditMessage {
+        channel: ChannelId,
+        message: MessageId,
+        edit: Value,
+    },
+    GetMember {
+        guild: GuildId,
+        user: UserId,
+    },
+    StartTyping(ChannelId),
+}
+
+/// Records every request instead of making it. Sent messages get increasing
+/// IDs, starting at [`RecordingDiscord::FIRST_MESSAGE`], and only the members
+/// given to [`RecordingDiscord::add_member`] are found.
+#[derive(Default)]
+pub struct RecordingDiscord {
+    calls: Mutex<Vec<Call>>,
+    sent: AtomicU64,
+    members: Mutex<Vec<Member>>,
+}
+
+impl RecordingDiscord {
+    pub const FIRST_MESSAGE: u64 = 1000;
+
+    /// Takes the calls made so far.
+    pub fn take(&self) -> Vec<Call> {
+        std::mem::take(&mut self.calls.lock().unwrap())
+    }
+
+    pub fn add_member(&self, member: Member) {
+        self.members.lock().unwrap().push(member);
+    }
+
+    fn record(&self, call: Call) {
+        self.calls.lock().unwrap().push(call);
+    }
+}
+
+fn filenames(files: &[AttachmentType<'_>]) -> Vec<String> {
+    files
+        .iter()
+        .map(|file| match file {
+            AttachmentType::Bytes { filename, .. } => filename.clone(),
+            AttachmentType::File { filename, .. } => filename.clone(),
+            other => format!("{other:?}"),
+        })
+        .collect()
+}
+
+#[async_trait]
+impl Discord for RecordingDiscord {
+    async fn respond<'a>(
+        &self,
+        interaction: InteractionId,
+        _token: &str,
+        response: CreateInteractionResponse<'a>,
+    ) -> serenity::Result<()> {
+        self.record(Call::Respond {
+            interaction,
+            response: Value::from(hashmap_to_json_map(response.0)),
+        });
+        Ok(())
+    }
+
+    async fn edit_response(
+        &self,
+        token: &str,
+        edit: EditInteractionResponse,
+    ) -> serenity::Result<()> {
+        self.record(Call::EditResponse {
+            token: token.to_owned(),
+            edit: Value::from(hashmap_to_json_map(edit.0)),
+        });
+        Ok(())
+    }
+
+    async fn send_message<'a>(
+        &self,
+        channel: ChannelId,
+        message: CreateMessage<'a>,
+    ) -> serenity::Result<MessageId> {
+        self.record(Call::SendMessage {
+            channel,
+            files: filenames(&message.2),
+            message: Value::from(hashmap_to_json_map(message.0)),
+        });
+        Ok(MessageId(
+            Self::FIRST_MESSAGE + self.sent.fetch_add(1, Ordering::SeqCst),
+        ))
+    }
+
+    async fn edit_message<'a>(
+        &self,
+        channel: ChannelId,
+        message: MessageId,
+        edit: EditMessage<'a>,
+    ) -> serenity::Result<()> {
+        self.record(Call::EditMessage {
+            channel,
+            message,
+            edit: Value::from(hashmap_to_json_map(edit.0)),
+        });
+        Ok(())
+    }
+
+    async fn member(&self, guild: GuildId, user: UserId) -> serenity::Result<Option<Member>> {
+        self.record(Call::GetMember { guild, user });
+        Ok(self
+            .members
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|member| member.guild_id == guild && member.user.id == user)
+            .cloned())
+    }
+
+    fn start_typing(&self, channel: ChannelId) -> serenity::Result<Typing> {
+        self.record(Call::StartTyping(channel));
+        Ok(Typing::none())
+    }
+}
+
+pub const GUILD: u64 = 500;
+
+fn user(id: u64, name: &str) -> Value {
+    json!({
+        "id": id.to_string(),
+        "username": name,
+        "discriminator": "0001",
+        "avatar": null,
+    })
+}
+
+/// A moderator with Manage Messages.
+fn moderator() -> Value {
+    json!({
+        "user": user(2, "Chabashira"),
+        "roles": [],
+        "joined_at": "2023-02-01T00:00:00.000000+00:00",
+        "deaf": false,
+        "mute": false,
+        "permissions": "8192",
+    })
+}
+
+/// A member of [`GUILD`] who joined at `joined_at`, an RFC 3339 timestamp,
+/// with no permissions.
+pub fn member(user_id: u64, username: &str, joined_at: &str, roles: &[u64]) -> Member {
+    serde_json::from_value(json!({
+        "user": user(user_id, username),
+        "guild_id": GUILD.to_string(),
+        "roles": roles.iter().map(u64::to_string).collect::<Vec<_>>(),
+        "joined_at": joined_at,
+        "deaf": false,
+        "mute": false,
+        "permissions": "0",
+    }))
+    .expect("Could not build member")
+}
+
+/// A slash command used in `channel`, with string and boolean options given
+/// in order.
+pub fn command(
+    name: &str,
+    user_id: u64,
+    username: &str,
+    channel: u64,
+    options: &[(&str, Value)],
+) -> ApplicationCommandInteraction {
+    let options = options
+        .iter()
+        .map(|(name, value)| {
+            json!({
+                "name": name,
+                "type": if value.is_boolean() { 5 } else { 3 },
+                "value": value,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    serde_json::from_value(json!({
+        "id": "10",
+        "application_id": "1",
+        "type": 2,
+        "data": {
+            "id": "20",
+            "name": name,
+            "type": 1,
+            "options": options,
+        },
+        "channel_id": channel.to_string(),
+        "user": user(user_id, username),
+        "token": "command-token",
+        "version": 1,
+        "locale": "en-US",
+    }))
+    .expect("Could not build command interaction")
+}
+
+/// A message in `channel`, as seen on the button that was pressed below it.
+fn message(id: u64, channel: u64) -> Value {
+    json!({
+        "id": id.to_string(),
+        "channel_id": channel.to_string(),
+        "author": user(1, "Valentine Bot"),
+        "content": "",
+        "timestamp": "2023-02-14T00:00:00.000000+00:00",
+        "edited_timestamp": null,
+        "tts": false,
+        "mention_everyone": false,
+        "mentions": [],
+        "mention_roles": [],
+        "attachments": [],
+        "embeds": [],
+        "pinned": false,
+        "type": 0,
+    })
+}
+
+/// A moderator pressing the button `custom_id` below `message_id`.
+pub fn button(custom_id: &str, message_id: u64, channel: u64) -> MessageComponentInteraction {
+    serde_json::from_value(json!({
+        "id": "11",
+        "application_id": "1",
+        "type": 3,
+        "data": {
+            "custom_id": custom_id,
+            "component_type": 2,
+        },
+        "guild_id": GUILD.to_string(),
+        "channel_id": channel.to_string(),
+        "member": moderator(),
+        "message": message(message_id, channel),
+        "token": "button-token",
+        "version": 1,
+        "locale": "en-US",
+    }))
+    .expect("Could not build button interaction")
+}
+
+/// A moderator submitting the modal `custom_id`, opened from a button below
+/// `message_id`, with one text input filled in with `value`.
+pub fn modal(
+    custom_id: &str,
+    input_id: &str,
+    value: &str,
+    message_id: u64,
+    channel: u64,
+) -> ModalSubmitInteraction {
+    serde_json::from_value(json!({
+        "id": "12",
+        "application_id": "1",
+        "type": 5,
+        "data": {
+            "custom_id": custom_id,
+            "components": [{
+                "type": 1,
+                "components": [{
+                    "type": 4,
+                    "custom_id": input_id,
+                    "value": value,
+                }],
+            }],
+        },
+        "guild_id": GUILD.to_string(),
+        "channel_id": channel.to_string(),
+        "member": moderator(),
+        "message": message(message_id, channel),
+        "token": "modal-token",
+        "version": 1,
+        "locale": "en-US",
+    }))
+    .expect("Could not build modal interaction")
+}
@@ -0,0 +1,36 @@
+use cotevalentines::duplicates::{similarity, SIMILARITY_THRESHOLD};
+
+const LETTER: &str = concat!(
+    "I have been meaning to tell you this for a while now, but every time I try ",
+    "the words simply will not come out. Happy Valentine's day!",
+);
+
+#[test]
+fn case_and_punctuation_do_not_matter() {
+    let shouted = LETTER.to_uppercase().replace([',', '.', '!'], "");
+
+    assert_eq!(similarity(LETTER, &shouted), 1.0);
+}
+
+#[test]
+fn a_changed_word_is_still_a_duplicate() {
+    let changed = LETTER.replace("simply", "just");
+
+    assert!(similarity(LETTER, &changed) >= SIMILARITY_THRESHOLD);
+}
+
+#[test]
+fn different_letters_are_not_duplicates() {
+    let other = concat!(
+        "Thank you for always being there for the class, even when nobody else ",
+        "would step up. I hope you have a wonderful Valentine's day!",
+    );
+
+    assert!(similarity(LETTER, other) < 0.1);
+}
+
+#[test]
+fn short_letters_are_compared_whole() {
+    assert_eq!(similarity("Hi there", "hi, there!"), 1.0);
+    assert_eq!(similarity("Hi there", "Bye there"), 0.0);
+}
@@ -0,0 +1,144 @@
+use chrono::{Duration, SecondsFormat, Utc};
+use cotevalentines::eligibility::Eligibility;
+use serde_json::json;
+use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::id::{GuildId, RoleId, UserId};
+
+mod common;
+
+use common::{command, member, Call, RecordingDiscord, GUILD};
+
+const LETTERS: u64 = 200;
+const SENDER_ROLE: u64 = 800;
+/// Discord's epoch, which snowflakes count from, in milliseconds.
+const DISCORD_EPOCH: i64 = 1_420_070_400_000;
+
+/// The ID of a user whose account is `days` old.
+fn created_days_ago(days: i64) -> u64 {
+    let created = Utc::now() - Duration::days(days);
+    ((created.timestamp_millis() - DISCORD_EPOCH) as u64) << 22
+}
+
+fn days_ago(days: i64) -> String {
+    (Utc::now() - Duration::days(days)).to_rfc3339_opts(SecondsFormat::Micros, false)
+}
+
+fn sendletter(user_id: u64) -> ApplicationCommandInteraction {
+    command(
+        "sendletter",
+        user_id,
+        "Ayanokouji",
+        LETTERS,
+        &[("recipient", json!("Kei")), ("anonymous", json!(false))],
+    )
+}
+
+fn in_guild(joined_days_ago: i64, roles: &[u64]) -> ApplicationCommandInteraction {
+    let mut interaction = sendletter(42);
+    interaction.guild_id = Some(GuildId(GUILD));
+    interaction.member = Some(member(42, "Ayanokouji", &days_ago(joined_days_ago), roles));
+    interaction
+}
+
+#[tokio::test]
+async fn new_accounts_wait_until_they_are_old_enough() {
+    let discord = RecordingDiscord::default();
+    let eligibility = Eligibility {
+        min_account_age_days: 7,
+        ..Default::default()
+    };
+
+    let refused = eligibility
+        .check(&sendletter(created_days_ago(2)), &discord)
+        .await
+        .unwrap()
+        .unwrap_err();
+    assert!(refused.starts_with("Your Discord account is too new to send letters."));
+
+    assert_eq!(
+        eligibility
+            .check(&sendletter(created_days_ago(8)), &discord)
+            .await
+            .unwrap(),
+        Ok(())
+    );
+    assert_eq!(discord.take(), vec![]);
+}
+
+#[tokio::test]
+async fn letters_from_dms_need_a_member_of_the_guild() {
+    let discord = RecordingDiscord::default();
+    let eligibility = Eligibility {
+        guild: Some(GuildId(GUILD)),
+        ..Default::default()
+    };
+
+    assert_eq!(
+        eligibility.check(&sendletter(42), &discord).await.unwrap(),
+        Err("Only members of the server can send letters.".to_owned())
+    );
+    assert_eq!(
+        discord.take(),
+        vec![Call::GetMember {
+            guild: GuildId(GUILD),
+            user: UserId(42),
+        }]
+    );
+
+    discord.add_member(member(42, "Ayanokouji", &days_ago(30), &[]));
+    assert_eq!(
+        eligibility.check(&sendletter(42), &discord).await.unwrap(),
+        Ok(())
+    );
+}
+
+#[tokio::test]
+async fn members_are_not_looked_up_in_the_guild() {
+    let discord = RecordingDiscord::default();
+    let eligibility = Eligibility {
+        guild: Some(GuildId(GUILD)),
+        min_member_days: 3,
+        ..Default::default()
+    };
+
+    let refused = eligibility
+        .check(&in_guild(1, &[]), &discord)
+        .await
+        .unwrap()
+        .unwrap_err();
+    assert!(refused.starts_with("You joined the server too recently to send letters."));
+
+    assert_eq!(
+        eligibility
+            .check(&in_guild(4, &[]), &discord)
+            .await
+            .unwrap(),
+        Ok(())
+    );
+    assert_eq!(discord.take(), vec![]);
+}
+
+#[tokio::test]
+async fn senders_need_the_sender_role_if_there_is_one() {
+    let discord = RecordingDiscord::default();
+    let eligibility = Eligibility {
+        guild: Some(GuildId(GUILD)),
+        sender_role: Some(RoleId(SENDER_ROLE)),
+        ..Default::default()
+    };
+
+    assert_eq!(
+        eligibility
+            .check(&in_guild(30, &[]), &discord)
+            .await
+            .unwrap(),
+        Err("You don't have the role needed to send letters.".to_owned())
+    );
+    assert_eq!(
+        eligibility
+            .check(&in_guild(30, &[SENDER_ROLE]), &discord)
+            .await
+            .unwrap(),
+        Ok(())
+    );
+}
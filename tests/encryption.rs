@@ -0,0 +1,180 @@
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use cotevalentines::encryption::{self, Key, Keyring, UNREADABLE};
+use cotevalentines::model::Letter;
+use cotevalentines::repository::{LetterRepository, Repository};
+use cotevalentines::schema::letters::dsl::{
+    anon, content, letters, message_id, recipient, sender, sender_id,
+};
+use diesel::prelude::*;
+
+mod common;
+
+use common::{letter, LETTER};
+
+fn key(byte: u8) -> Key {
+    Key::from_base64(&BASE64.encode([byte; 32])).unwrap()
+}
+
+const OLD: u8 = 1;
+const NEW: u8 = 2;
+
+/// A repository whose letters are encrypted with the new key, and can still
+/// be read if they were encrypted with the old one. Every test uses the same
+/// keys, as they are shared by the whole test binary.
+fn repository() -> Repository {
+    encryption::use_keyring(Keyring {
+        current: Some(key(NEW)),
+        previous: Some(key(OLD)),
+    });
+    common::repository()
+}
+
+/// The ID of the key a stored value was encrypted with.
+fn key_id(stored: &str) -> &str {
+    stored
+        .strip_prefix("enc:v1:")
+        .and_then(|sealed| sealed.split(':').next())
+        .unwrap_or_else(|| panic!("{stored:?} is not encrypted"))
+}
+
+/// The ID stored with values encrypted with `key`.
+fn id_of(key: &Key) -> String {
+    key_id(&key.seal("")).to_owned()
+}
+
+/// The content, sender and sender ID of every letter as they are stored.
+async fn stored(db: &Repository) -> Vec<(String, String, String)> {
+    db.query(|conn| letters.select((content, sender, sender_id)).load(conn))
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn letters_and_their_senders_are_encrypted_at_rest() {
+    let db = repository();
+    db.add_letter(letter("Ayanokouji", "Karuizawa Kei"), Some("100".into()))
+        .await
+        .unwrap();
+
+    let new_id = id_of(&key(NEW));
+    for value in stored(&db)
+        .await
+        .into_iter()
+        .flat_map(|(c, s, i)| [c, s, i])
+    {
+        assert_eq!(key_id(&value), new_id);
+        assert!(!value.contains("Kei") && !value.contains("Ayanokouji"));
+    }
+
+    assert_eq!(db.letters_sent_by("Ayanokouji".into()).await.unwrap(), 1);
+    let duplicate = db
+        .find_duplicate("Ayanokouji#id".into(), LETTER.into())
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(duplicate.own);
+
+    let deleted = db.delete_letter("100".into()).await.unwrap().unwrap();
+    assert_eq!(deleted.sender, "Ayanokouji");
+    assert_eq!(deleted.sender_id, "Ayanokouji#id");
+    assert_eq!(deleted.content, LETTER);
+}
+
+#[tokio::test]
+async fn rotating_the_key_encrypts_plaintext_and_old_letters() {
+    let db = repository();
+    let old = key(OLD);
+    db.query(move |conn| {
+        diesel::insert_into(letters)
+            .values(vec![
+                (
+                    recipient.eq("Karuizawa Kei"),
+                    sender.eq("Ayanokouji".to_owned()),
+                    anon.eq(false),
+                    content.eq(LETTER.to_owned()),
+                    sender_id.eq("42".to_owned()),
+                ),
+                (
+                    recipient.eq("Horikita Suzune"),
+                    sender.eq(old.seal("Kushida")),
+                    anon.eq(true),
+                    content.eq(old.seal(LETTER)),
+                    sender_id.eq(old.seal("43")),
+                ),
+            ])
+            .execute(conn)
+    })
+    .await
+    .unwrap();
+
+    // both can be read before they are rotated...
+    let before = db.query(|conn| letters.load::<Letter>(conn)).await.unwrap();
+    assert_eq!(before[0].sender, "Ayanokouji");
+    assert_eq!(before[1].sender, "Kushida");
+    assert_eq!(before[1].content, LETTER);
+
+    let rotated = db.query(encryption::rotate).await.unwrap();
+    assert_eq!(rotated, 2);
+
+    // ...and after, when they are only encrypted with the new key
+    let new_id = id_of(&key(NEW));
+    for value in stored(&db)
+        .await
+        .into_iter()
+        .flat_map(|(c, s, i)| [c, s, i])
+    {
+        assert_eq!(key_id(&value), new_id);
+    }
+    let after = db.query(|conn| letters.load::<Letter>(conn)).await.unwrap();
+    assert_eq!(after[0].sender, "Ayanokouji");
+    assert_eq!(after[0].content, LETTER);
+    assert_eq!(after[1].sender, "Kushida");
+    assert_eq!(after[1].sender_id, "43");
+}
+
+#[tokio::test]
+async fn letters_encrypted_with_an_unknown_key_are_shown_but_not_acted_on() {
+    let db = repository();
+    let unknown = key(3);
+    let unknown_id = id_of(&unknown);
+    db.add_letter(letter("Kushida", "Horikita Suzune"), None)
+        .await
+        .unwrap();
+    db.query(move |conn| {
+        diesel::insert_into(letters)
+            .values((
+                recipient.eq("Karuizawa Kei"),
+                sender.eq(unknown.seal("Ayanokouji")),
+                anon.eq(false),
+                content.eq(unknown.seal(LETTER)),
+                message_id.eq("100"),
+                sender_id.eq(unknown.seal("42")),
+            ))
+            .execute(conn)
+    })
+    .await
+    .unwrap();
+
+    let loaded = db.query(|conn| letters.load::<Letter>(conn)).await.unwrap();
+    assert_eq!(loaded[0].sender, "Kushida");
+    assert_eq!(loaded[1].sender, UNREADABLE);
+    assert_eq!(loaded[1].content, UNREADABLE);
+
+    assert!(db.letters_sent_by("Kushida".into()).await.is_err());
+    assert!(db
+        .find_duplicate("Kushida#id".into(), LETTER.into())
+        .await
+        .is_err());
+    assert!(db
+        .block_sender("100".into(), "Spam".into(), "Chabashira".into())
+        .await
+        .is_err());
+    assert!(!db.is_blocked(UNREADABLE.into()).await.unwrap());
+
+    let error = db.query(encryption::rotate).await.err().unwrap();
+    assert!(error
+        .to_string()
+        .contains("letter 2 could not be decrypted"));
+    assert_eq!(key_id(&stored(&db).await[1].1), unknown_id);
+}
@@ -0,0 +1,114 @@
+use cotevalentines::commands::send::ValentineLetter;
+use cotevalentines::export::{export, load_letters, ExportFormat};
+use cotevalentines::import::{import, ImportFormat};
+use cotevalentines::repository::Repository;
+use serde_json::{json, Value};
+
+mod common;
+
+use common::{letter, repository, with_letters, LETTER};
+
+/// A signed letter to Kei and an anonymous one to Horikita.
+fn letters() -> [ValentineLetter; 2] {
+    [
+        letter("Ayanokouji", "Karuizawa Kei"),
+        ValentineLetter {
+            anon: true,
+            ..letter("Kushida", "Horikita Suzune")
+        },
+    ]
+}
+
+async fn exported(db: &Repository, format: ExportFormat, redact: bool) -> Vec<u8> {
+    let letters = db.query(load_letters).await.unwrap();
+    export(&letters, format, redact).unwrap()
+}
+
+#[tokio::test]
+async fn json_exports_hide_anonymous_senders_when_redacted() {
+    let db = with_letters(letters()).await;
+
+    let full: Value =
+        serde_json::from_slice(&exported(&db, ExportFormat::Json, false).await).unwrap();
+    let redacted: Value =
+        serde_json::from_slice(&exported(&db, ExportFormat::Json, true).await).unwrap();
+
+    assert_eq!(
+        full[0],
+        json!({
+            "id": 1,
+            "recipient": "Karuizawa Kei",
+            "sender": "Ayanokouji",
+            "sender_id": "Ayanokouji#id",
+            "anon": false,
+            "content": LETTER,
+            "created_at": "2023-02-14T12:00:00.000Z",
+        })
+    );
+    assert_eq!(full[1]["sender"], "Kushida");
+    assert_eq!(redacted[0], full[0]);
+    assert_eq!(redacted[1]["sender"], Value::Null);
+    assert_eq!(redacted[1]["sender_id"], Value::Null);
+    assert_eq!(redacted[1]["content"], LETTER);
+}
+
+#[tokio::test]
+async fn csv_exports_have_a_column_per_field() {
+    let db = with_letters(letters()).await;
+
+    let csv = String::from_utf8(exported(&db, ExportFormat::Csv, true).await).unwrap();
+    let lines = csv.lines().collect::<Vec<_>>();
+
+    assert_eq!(
+        lines[0],
+        "id,recipient,sender,sender_id,anon,content,created_at"
+    );
+    assert_eq!(
+        lines[1],
+        format!(
+            "1,Karuizawa Kei,Ayanokouji,Ayanokouji#id,false,\"{LETTER}\",2023-02-14T12:00:00.000Z"
+        )
+    );
+    assert_eq!(
+        lines[2],
+        format!("2,Horikita Suzune,,,true,\"{LETTER}\",2023-02-14T12:00:00.000Z")
+    );
+}
+
+#[tokio::test]
+async fn markdown_exports_group_letters_by_recipient() {
+    let db = with_letters(letters()).await;
+
+    let full = String::from_utf8(exported(&db, ExportFormat::Markdown, false).await).unwrap();
+    let redacted = String::from_utf8(exported(&db, ExportFormat::Markdown, true).await).unwrap();
+
+    assert!(full.contains(&format!(
+        "## To Horikita Suzune\n\n### Letter #2 from Kushida (anonymous)\n\n{LETTER}\n"
+    )));
+    assert!(full.contains("## To Karuizawa Kei\n\n### Letter #1 from Ayanokouji\n"));
+    assert!(full.find("Horikita Suzune") < full.find("Karuizawa Kei"));
+    assert!(redacted.contains("### Letter #2 from Anonymous\n"));
+}
+
+#[tokio::test]
+async fn exports_can_be_imported_again() {
+    let db = with_letters(letters()).await;
+    let json = exported(&db, ExportFormat::Json, false).await;
+    let csv = exported(&db, ExportFormat::Csv, false).await;
+
+    for (file, format) in [(json, ImportFormat::Json), (csv, ImportFormat::Csv)] {
+        let copy = repository();
+        let report = copy
+            .run(move |conn| import(conn, file.as_slice(), format, false))
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(report.letters, 2);
+        assert!(report.errors.is_empty());
+        assert_eq!(
+            exported(&copy, ExportFormat::Json, false).await,
+            exported(&db, ExportFormat::Json, false).await
+        );
+    }
+}
@@ -0,0 +1,143 @@
+use cotevalentines::filter::{
+    neutralize_mentions, strip_mentions, ContentFilter, FilterConfig, RuleConfig,
+};
+
+fn filter(config: &str) -> ContentFilter {
+    ContentFilter::new(serde_json::from_str::<FilterConfig>(config).unwrap()).unwrap()
+}
+
+fn words(words: &[&str]) -> RuleConfig {
+    RuleConfig {
+        words: words.iter().map(|word| word.to_string()).collect(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn nothing_is_filtered_by_default() {
+    let letter = "Check out https://discord.gg/abc <@123> @everyone";
+
+    let screened = ContentFilter::default().screen(letter).unwrap();
+
+    assert_eq!(screened.letter, letter);
+    assert!(screened.flags.is_empty());
+}
+
+#[test]
+fn words_are_matched_whole_and_ignoring_case() {
+    let filter = ContentFilter::new(FilterConfig {
+        reject: words(&["ass"]),
+        ..Default::default()
+    })
+    .unwrap();
+
+    assert!(filter.screen("You ASS!").is_err());
+    assert!(filter
+        .screen("See you in class, after the assembly")
+        .is_ok());
+}
+
+#[test]
+fn rejections_do_not_repeat_the_letter() {
+    let filter =
+        filter(r#"{ "reject": { "words": ["idiot"], "patterns": ["(?i)free\\s+nitro"] } }"#);
+
+    assert_eq!(
+        filter.screen("you idiot").unwrap_err(),
+        "contains a word that is not allowed"
+    );
+    assert_eq!(
+        filter.screen("FREE   nitro here").unwrap_err(),
+        "contains something that is not allowed"
+    );
+}
+
+#[test]
+fn flagged_letters_are_accepted_with_reasons() {
+    let filter = filter(
+        r#"{
+            "reject": { "invites": true },
+            "flag": { "words": ["hate"], "patterns": ["\\d{4,}"], "links": true }
+        }"#,
+    );
+
+    let screened = filter
+        .screen("I hate that I love you, call 5551234 or see https://example.com")
+        .unwrap();
+
+    assert_eq!(
+        screened.flags,
+        [
+            "contains the word \"hate\"",
+            "matches `\\d{4,}`",
+            "contains a link"
+        ]
+    );
+}
+
+#[test]
+fn invites_are_not_also_links() {
+    let filter = filter(r#"{ "flag": { "links": true, "invites": true } }"#);
+
+    let flags = |letter| filter.screen(letter).unwrap().flags;
+
+    assert_eq!(flags("join discord.gg/cote"), ["contains a server invite"]);
+    assert_eq!(
+        flags("https://discord.com/invite/cote-server"),
+        ["contains a server invite"]
+    );
+    assert_eq!(flags("see www.example.com"), ["contains a link"]);
+    assert!(flags("no links here, discord is fun").is_empty());
+}
+
+#[test]
+fn invites_can_be_rejected() {
+    let filter = filter(r#"{ "reject": { "invites": true } }"#);
+
+    assert_eq!(
+        filter
+            .screen("come to discordapp.com/invite/xyz")
+            .unwrap_err(),
+        "contains a server invite"
+    );
+}
+
+#[test]
+fn mentions_are_stripped_before_screening() {
+    let filter = filter(r#"{ "strip_mentions": true, "reject": { "words": ["everyone"] } }"#);
+
+    let screened = filter
+        .screen("Hey <@123> and <@!456>, tell <@&789> @everyone @here")
+        .unwrap();
+
+    assert_eq!(screened.letter, "Hey and, tell");
+}
+
+#[test]
+fn strip_mentions_keeps_the_rest() {
+    assert_eq!(strip_mentions("<@1> Dear Kei,"), "Dear Kei,");
+    assert_eq!(
+        strip_mentions("mail me at kei@example.com"),
+        "mail me at kei@example.com"
+    );
+}
+
+#[test]
+fn unknown_settings_are_refused() {
+    assert!(serde_json::from_str::<FilterConfig>(r#"{ "rejct": {} }"#).is_err());
+}
+
+#[test]
+fn neutralized_mentions_do_not_ping() {
+    assert_eq!(
+        neutralize_mentions("@everyone @here <@123> <@!456> <@&789>"),
+        "@\u{200B}everyone @\u{200B}here <@\u{200B}123> <@\u{200B}!456> <@\u{200B}&789>"
+    );
+}
+
+#[test]
+fn neutralize_mentions_keeps_the_rest() {
+    let text = "Dear Kei, mail me at kei@example.com <#123> :heart: @ you";
+
+    assert_eq!(neutralize_mentions(text), text);
+}
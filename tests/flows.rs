@@ -0,0 +1,724 @@
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::Duration;
+
+use cotevalentines::audit::{self, EventFilter};
+use cotevalentines::commands::send::{SendLetterCommand, ValentineLetter};
+use cotevalentines::commands::{block, delete, publish, CommandError};
+use cotevalentines::cooldown::Cooldown;
+use cotevalentines::eligibility::Eligibility;
+use cotevalentines::filter::{ContentFilter, FilterConfig, RuleConfig};
+use cotevalentines::repository::{LetterRepository, Repository};
+use diesel::RunQueryDsl;
+use serde_json::json;
+use serenity::model::id::{ChannelId, GuildId, InteractionId, MessageId, UserId};
+
+mod common;
+
+use common::{
+    button, command, letter, member, modal, repository, Call, RecordingDiscord, GUILD, LETTER,
+};
+
+const AUDIT: u64 = 300;
+const LETTERS: u64 = 301;
+const FIRST: u64 = RecordingDiscord::FIRST_MESSAGE;
+
+fn send_letter(audit_channel: Option<u64>) -> SendLetterCommand {
+    SendLetterCommand {
+        letters_allowed: Arc::new(AtomicBool::new(true)),
+        audit_channel: audit_channel.map(ChannelId),
+        filter: ContentFilter::default(),
+        cooldown: Cooldown::new(Duration::ZERO),
+        eligibility: Eligibility::default(),
+    }
+}
+
+async fn send(
+    discord: &RecordingDiscord,
+    db: &Repository,
+    recipient: &str,
+) -> Result<String, CommandError> {
+    let interaction = command(
+        "sendletter",
+        42,
+        "Ayanokouji",
+        LETTERS,
+        &[
+            ("recipient", json!(recipient)),
+            ("letter", json!(LETTER)),
+            ("anonymous", json!(true)),
+        ],
+    );
+    send_letter(Some(AUDIT))
+        .send(&interaction, discord, db)
+        .await
+}
+
+#[tokio::test]
+async fn send_log_and_delete_a_letter() {
+    let db = repository();
+    let discord = RecordingDiscord::default();
+
+    let reply = send(&discord, &db, "Karuizawa Kei").await.unwrap();
+    assert_eq!(reply, "Thank you for your message, it has been recorded.");
+
+    let calls = discord.take();
+    let [Call::SendMessage {
+        channel,
+        message,
+        files,
+    }] = calls.as_slice()
+    else {
+        panic!("expected the letter to be logged, got {calls:?}");
+    };
+    assert_eq!(*channel, ChannelId(AUDIT));
+    assert!(files.is_empty());
+    let embed = &message["embeds"][0];
+    assert_eq!(
+        embed["title"],
+        "Sent anonymously by Ayanokouji to Karuizawa Kei"
+    );
+    assert_eq!(embed["description"], LETTER);
+    assert_eq!(embed["fields"][0]["value"], "42");
+    assert!(embed["fields"][1]["value"]
+        .as_str()
+        .unwrap()
+        .starts_with("<t:"));
+    assert_eq!(embed["fields"][2]["value"], "DMs");
+    assert_eq!(
+        message["components"][0]["components"][0]["custom_id"],
+        "delete_letter"
+    );
+    assert_eq!(db.letters_sent_by("Ayanokouji".into()).await.unwrap(), 1);
+
+    // a moderator presses delete below the logged letter...
+    delete::handle_button(&button("delete_letter", FIRST, AUDIT), &discord)
+        .await
+        .unwrap();
+    let calls = discord.take();
+    let [Call::Respond {
+        interaction,
+        response,
+    }] = calls.as_slice()
+    else {
+        panic!("expected a confirmation modal, got {calls:?}");
+    };
+    assert_eq!(*interaction, InteractionId(11));
+    assert_eq!(response["type"], 9);
+    assert_eq!(response["data"]["custom_id"], "delete_modal");
+    let input = &response["data"]["components"][0]["components"][0];
+    assert_eq!(input["custom_id"], FIRST.to_string());
+
+    // ...and confirms
+    let confirm = modal("delete_modal", &FIRST.to_string(), "", FIRST, AUDIT);
+    delete::handle_modal(&confirm, &discord, &db).await.unwrap();
+    let calls = discord.take();
+    let [Call::EditMessage {
+        channel,
+        message,
+        edit,
+    }, Call::Respond { response, .. }] = calls.as_slice()
+    else {
+        panic!("expected the logged letter to be edited, got {calls:?}");
+    };
+    assert_eq!((*channel, *message), (ChannelId(AUDIT), MessageId(FIRST)));
+    assert_eq!(
+        edit["embeds"][0]["title"],
+        "Deleted: Sent anonymously by Ayanokouji to Karuizawa Kei"
+    );
+    assert_eq!(edit["components"], json!([]));
+    assert_eq!(response["data"]["content"], "Deleted a message");
+    assert_eq!(response["data"]["flags"], 64);
+    assert_eq!(db.letters_sent_by("Ayanokouji".into()).await.unwrap(), 0);
+    let logged = db
+        .query(|conn| audit::events(conn, &EventFilter::default(), 10))
+        .await
+        .unwrap();
+    assert_eq!(logged.len(), 1);
+    assert_eq!(
+        (logged[0].action.as_str(), logged[0].actor_id.as_str()),
+        ("delete", "2")
+    );
+    assert_eq!(logged[0].target.as_deref(), Some("letter #1"));
+
+    // confirming twice does not delete anything else
+    let again = delete::handle_modal(&confirm, &discord, &db).await;
+    assert!(matches!(again, Err(CommandError::User(_))));
+    assert!(discord.take().is_empty());
+}
+
+#[tokio::test]
+async fn letters_over_the_quota_are_not_logged() {
+    let db = repository();
+    let discord = RecordingDiscord::default();
+
+    send(&discord, &db, "Karuizawa Kei").await.unwrap();
+    send(&discord, &db, "Horikita Suzune").await.unwrap();
+    let reply = send(&discord, &db, "Kushida Kikyou").await.unwrap();
+
+    assert_eq!(reply, "You have already sent two messages.");
+    assert_eq!(discord.take().len(), 2);
+    assert_eq!(db.letters_sent_by("Ayanokouji".into()).await.unwrap(), 2);
+}
+
+#[tokio::test]
+async fn letters_are_kept_without_an_audit_channel() {
+    let db = repository();
+    let discord = RecordingDiscord::default();
+    let interaction = command(
+        "sendletter",
+        42,
+        "Ayanokouji",
+        LETTERS,
+        &[
+            ("recipient", json!("Karuizawa Kei")),
+            ("letter", json!(LETTER)),
+            ("anonymous", json!(false)),
+        ],
+    );
+
+    send_letter(None)
+        .send(&interaction, &discord, &db)
+        .await
+        .unwrap();
+
+    assert!(discord.take().is_empty());
+    assert_eq!(db.letters_sent_by("Ayanokouji".into()).await.unwrap(), 1);
+}
+
+#[tokio::test]
+async fn filtered_letters_are_rejected_or_flagged() {
+    let db = repository();
+    let discord = RecordingDiscord::default();
+    let command_with = |filter| SendLetterCommand {
+        filter,
+        ..send_letter(Some(AUDIT))
+    };
+    let words = |words: &[&str]| RuleConfig {
+        words: words.iter().map(|word| word.to_string()).collect(),
+        ..Default::default()
+    };
+    let interaction = command(
+        "sendletter",
+        42,
+        "Ayanokouji",
+        LETTERS,
+        &[
+            ("recipient", json!("Karuizawa Kei")),
+            ("letter", json!(LETTER)),
+            ("anonymous", json!(false)),
+        ],
+    );
+
+    let rejecting = ContentFilter::new(FilterConfig {
+        reject: words(&["valentine's"]),
+        ..Default::default()
+    })
+    .unwrap();
+    let rejected = command_with(rejecting)
+        .send(&interaction, &discord, &db)
+        .await;
+    assert!(matches!(rejected, Err(CommandError::User(_))));
+    assert!(discord.take().is_empty());
+    assert_eq!(db.letters_sent_by("Ayanokouji".into()).await.unwrap(), 0);
+
+    let flagging = ContentFilter::new(FilterConfig {
+        flag: words(&["valentine's"]),
+        ..Default::default()
+    })
+    .unwrap();
+    command_with(flagging)
+        .send(&interaction, &discord, &db)
+        .await
+        .unwrap();
+    let calls = discord.take();
+    let [Call::SendMessage { message, .. }] = calls.as_slice() else {
+        panic!("expected the letter to be logged, got {calls:?}");
+    };
+    let review = &message["embeds"][0]["fields"][3];
+    assert_eq!(review["name"], "⚠ Needs review");
+    assert_eq!(review["value"], "contains the word \"Valentine's\"");
+    assert_eq!(db.letters_sent_by("Ayanokouji".into()).await.unwrap(), 1);
+}
+
+#[tokio::test]
+async fn letters_too_short_without_their_mentions_are_rejected() {
+    let db = repository();
+    let discord = RecordingDiscord::default();
+    let sendletter = SendLetterCommand {
+        filter: ContentFilter::new(FilterConfig {
+            strip_mentions: true,
+            ..Default::default()
+        })
+        .unwrap(),
+        ..send_letter(Some(AUDIT))
+    };
+    let letter = format!("{}Happy Valentine's day!", "<@123> ".repeat(15));
+    let interaction = command(
+        "sendletter",
+        42,
+        "Ayanokouji",
+        LETTERS,
+        &[
+            ("recipient", json!("Karuizawa Kei")),
+            ("letter", json!(letter)),
+            ("anonymous", json!(false)),
+        ],
+    );
+
+    let rejected = sendletter.send(&interaction, &discord, &db).await;
+    assert!(matches!(
+        rejected,
+        Err(CommandError::User(message)) if message == "Your letter was not accepted \
+            without its mentions: Letter must be at least 100 characters long."
+    ));
+    assert!(discord.take().is_empty());
+    assert_eq!(db.letters_sent_by("Ayanokouji".into()).await.unwrap(), 0);
+}
+
+#[tokio::test(start_paused = true)]
+async fn mentions_in_letters_do_not_ping() {
+    let db = repository();
+    let discord = RecordingDiscord::default();
+    let letter = format!("{LETTER} @everyone <@123>");
+    let interaction = command(
+        "sendletter",
+        42,
+        "Ayanokouji",
+        LETTERS,
+        &[
+            ("recipient", json!("<@&456>")),
+            ("letter", json!(letter)),
+            ("anonymous", json!(false)),
+        ],
+    );
+    let neutralized = format!("{LETTER} @\u{200B}everyone <@\u{200B}123>");
+
+    send_letter(Some(AUDIT))
+        .send(&interaction, &discord, &db)
+        .await
+        .unwrap();
+    let publish = command("publish", 2, "Chabashira", LETTERS, &[]);
+    publish::publish(&publish, &discord, &db).await.unwrap();
+    let confirm = modal("delete_modal", &FIRST.to_string(), "", FIRST, AUDIT);
+    delete::handle_modal(&confirm, &discord, &db).await.unwrap();
+
+    let calls = discord.take();
+    let messages = calls
+        .iter()
+        .filter_map(|call| match call {
+            Call::SendMessage { message, .. } => Some(message),
+            Call::EditMessage { edit, .. } => Some(edit),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    // logged, published and marked as deleted
+    assert_eq!(messages.len(), 3);
+    for message in &messages {
+        assert_eq!(message["allowed_mentions"], json!({ "parse": [] }));
+        let title = message["embeds"][0]["title"].as_str().unwrap();
+        assert!(title.ends_with("<@\u{200B}&456>"), "{title}");
+    }
+    assert_eq!(messages[0]["embeds"][0]["description"], neutralized);
+    assert_eq!(messages[1]["embeds"][0]["description"], neutralized);
+
+    let answers = calls.iter().filter_map(|call| match call {
+        Call::Respond { response, .. } => Some(&response["data"]),
+        Call::EditResponse { edit, .. } => Some(edit),
+        _ => None,
+    });
+    for answer in answers.filter(|answer| !answer.is_null()) {
+        assert_eq!(answer["allowed_mentions"], json!({ "parse": [] }));
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn publish_sends_every_letter() {
+    let db = repository();
+    let discord = RecordingDiscord::default();
+    db.add_letter(letter("Ichinose", "Kanzaki"), None)
+        .await
+        .unwrap();
+    db.add_letter(
+        ValentineLetter {
+            anon: true,
+            ..letter("Ryuuen", "Ibuki")
+        },
+        None,
+    )
+    .await
+    .unwrap();
+
+    let interaction = command("publish", 2, "Chabashira", LETTERS, &[]);
+    publish::publish(&interaction, &discord, &db).await.unwrap();
+
+    let calls = discord.take();
+    let [Call::Respond { response, .. }, Call::StartTyping(typing), Call::SendMessage {
+        channel: first_channel,
+        message: first,
+        files: first_files,
+    }, Call::SendMessage {
+        message: second, ..
+    }, Call::EditResponse { token, edit }] = calls.as_slice()
+    else {
+        panic!("unexpected calls while publishing: {calls:?}");
+    };
+    // deferred, since publishing takes a while
+    assert_eq!(response["type"], 5);
+    assert_eq!(*typing, ChannelId(LETTERS));
+    assert_eq!(*first_channel, ChannelId(LETTERS));
+    assert!(first_files.is_empty());
+    assert_eq!(first["embeds"][0]["title"], "From Ichinose to Kanzaki");
+    assert_eq!(first["embeds"][0]["description"], LETTER);
+    // anonymous letters do not show the sender
+    assert_eq!(second["embeds"][0]["title"], "To Ibuki");
+    assert_eq!(token, "command-token");
+    assert_eq!(edit["content"], "Done");
+}
+
+#[tokio::test(start_paused = true)]
+async fn publish_goes_by_submission_time() {
+    let db = repository();
+    let discord = RecordingDiscord::default();
+    let later = letter("Ichinose", "Kanzaki");
+    let earlier = ValentineLetter {
+        sent_at: later.sent_at - chrono::Duration::hours(1),
+        ..letter("Ryuuen", "Ibuki")
+    };
+    db.add_letter(later, None).await.unwrap();
+    db.add_letter(earlier, None).await.unwrap();
+
+    let interaction = command("publish", 2, "Chabashira", LETTERS, &[]);
+    publish::publish(&interaction, &discord, &db).await.unwrap();
+
+    let titles = discord
+        .take()
+        .into_iter()
+        .filter_map(|call| match call {
+            Call::SendMessage { message, .. } => Some(message["embeds"][0]["title"].clone()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(titles, ["From Ryuuen to Ibuki", "From Ichinose to Kanzaki"]);
+}
+
+#[tokio::test(start_paused = true)]
+async fn publish_attaches_cards() {
+    let db = repository();
+    let discord = RecordingDiscord::default();
+    let id = db
+        .add_letter(letter("Ichinose", "Kanzaki"), None)
+        .await
+        .unwrap();
+
+    let interaction = command(
+        "publish",
+        2,
+        "Chabashira",
+        LETTERS,
+        &[("cards", json!(true))],
+    );
+    publish::publish(&interaction, &discord, &db).await.unwrap();
+
+    let sent = discord
+        .take()
+        .into_iter()
+        .filter_map(|call| match call {
+            Call::SendMessage { message, files, .. } => Some((message, files)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let filename = format!("letter-{id}.png");
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].1, [format!("letter-{id}.png")]);
+    assert_eq!(
+        sent[0].0["embeds"][0]["image"]["url"],
+        format!("attachment://{filename}")
+    );
+}
+
+#[tokio::test]
+async fn publish_without_letters_fails() {
+    let db = repository();
+    let discord = RecordingDiscord::default();
+
+    let interaction = command("publish", 2, "Chabashira", LETTERS, &[]);
+    let published = publish::publish(&interaction, &discord, &db).await;
+
+    assert!(matches!(published, Err(CommandError::User(_))));
+    // only the deferred answer, which the error report follows up on
+    let calls = discord.take();
+    assert!(matches!(calls.as_slice(), [Call::Respond { .. }]));
+}
+
+#[tokio::test]
+async fn blocked_senders_cannot_send_letters() {
+    let db = repository();
+    let discord = RecordingDiscord::default();
+
+    send(&discord, &db, "Karuizawa Kei").await.unwrap();
+    let calls = discord.take();
+    let [Call::SendMessage { message, .. }] = calls.as_slice() else {
+        panic!("expected the letter to be logged, got {calls:?}");
+    };
+    assert_eq!(
+        message["components"][0]["components"][4]["custom_id"],
+        "block_sender"
+    );
+
+    // a moderator blocks the sender from below the logged letter...
+    block::handle_button(&button("block_sender", FIRST, AUDIT), &discord)
+        .await
+        .unwrap();
+    let calls = discord.take();
+    let [Call::Respond { response, .. }] = calls.as_slice() else {
+        panic!("expected a modal asking why, got {calls:?}");
+    };
+    assert_eq!(response["data"]["custom_id"], "block_modal");
+    let input = &response["data"]["components"][0]["components"][0];
+    assert_eq!(input["custom_id"], FIRST.to_string());
+
+    let reason = modal("block_modal", &FIRST.to_string(), "Spam", FIRST, AUDIT);
+    block::handle_modal(&reason, &discord, &db).await.unwrap();
+    let calls = discord.take();
+    let [Call::Respond { response, .. }] = calls.as_slice() else {
+        panic!("expected a confirmation, got {calls:?}");
+    };
+    assert_eq!(
+        response["data"]["content"],
+        "Ayanokouji can no longer send letters"
+    );
+    assert!(db.is_blocked("42".into()).await.unwrap());
+    let logged = db
+        .query(|conn| audit::events(conn, &EventFilter::default(), 10))
+        .await
+        .unwrap();
+    assert_eq!(logged[0].target.as_deref(), Some("user 42"));
+    assert_eq!(logged[0].details.as_deref(), Some("Spam"));
+
+    // ...who is turned down before their quota is even looked at
+    let blocked = send(&discord, &db, "Horikita Suzune").await;
+    assert!(matches!(
+        blocked,
+        Err(CommandError::User(message)) if message == "You are not allowed to send letters."
+    ));
+    assert!(discord.take().is_empty());
+    assert_eq!(db.letters_sent_by("Ayanokouji".into()).await.unwrap(), 1);
+}
+
+#[tokio::test]
+async fn deleted_letters_cannot_be_blocked_from() {
+    let db = repository();
+    let discord = RecordingDiscord::default();
+
+    let reason = modal("block_modal", &FIRST.to_string(), "Spam", FIRST, AUDIT);
+    let blocked = block::handle_modal(&reason, &discord, &db).await;
+
+    assert!(matches!(blocked, Err(CommandError::User(_))));
+    assert!(discord.take().is_empty());
+}
+
+#[tokio::test(start_paused = true)]
+async fn senders_wait_between_letters() {
+    let db = repository();
+    let discord = RecordingDiscord::default();
+    let sendletter = SendLetterCommand {
+        cooldown: Cooldown::default(),
+        ..send_letter(Some(AUDIT))
+    };
+    let to = |recipient: &str| {
+        command(
+            "sendletter",
+            42,
+            "Ayanokouji",
+            LETTERS,
+            &[
+                ("recipient", json!(recipient)),
+                ("letter", json!(LETTER)),
+                ("anonymous", json!(true)),
+            ],
+        )
+    };
+
+    sendletter
+        .send(&to("Karuizawa Kei"), &discord, &db)
+        .await
+        .unwrap();
+    tokio::time::advance(Duration::from_secs(15)).await;
+    let again = sendletter.send(&to("Horikita Suzune"), &discord, &db).await;
+    assert!(matches!(
+        again,
+        Err(CommandError::User(message))
+            if message == "Please wait 45 seconds before sending another letter."
+    ));
+    assert_eq!(discord.take().len(), 1);
+
+    tokio::time::advance(Duration::from_secs(45)).await;
+    sendletter
+        .send(&to("Horikita Suzune"), &discord, &db)
+        .await
+        .unwrap();
+    assert_eq!(db.letters_sent_by("Ayanokouji".into()).await.unwrap(), 2);
+}
+
+#[tokio::test(start_paused = true)]
+async fn letters_that_could_not_be_stored_do_not_start_the_cooldown() {
+    let db = repository();
+    let discord = RecordingDiscord::default();
+    let sendletter = SendLetterCommand {
+        cooldown: Cooldown::new(Duration::from_secs(60)),
+        ..send_letter(None)
+    };
+    let interaction = command(
+        "sendletter",
+        42,
+        "Ayanokouji",
+        LETTERS,
+        &[
+            ("recipient", json!("Karuizawa Kei")),
+            ("letter", json!(LETTER)),
+            ("anonymous", json!(true)),
+        ],
+    );
+    let fail_inserts = |fail: bool| {
+        db.query(move |conn| {
+            diesel::sql_query(if fail {
+                "CREATE TRIGGER full BEFORE INSERT ON letters \
+                 BEGIN SELECT RAISE(FAIL, 'database or disk is full'); END"
+            } else {
+                "DROP TRIGGER full"
+            })
+            .execute(conn)
+        })
+    };
+
+    fail_inserts(true).await.unwrap();
+    let failed = sendletter.send(&interaction, &discord, &db).await;
+    assert!(matches!(failed, Err(CommandError::Database(_))));
+
+    fail_inserts(false).await.unwrap();
+    sendletter.send(&interaction, &discord, &db).await.unwrap();
+    assert_eq!(db.letters_sent_by("Ayanokouji".into()).await.unwrap(), 1);
+}
+
+#[tokio::test]
+async fn duplicates_are_accepted_but_flagged() {
+    let db = repository();
+    let discord = RecordingDiscord::default();
+    db.add_letter(letter("Ichinose", "Kanzaki"), None)
+        .await
+        .unwrap();
+    let own = db
+        .add_letter(
+            ValentineLetter {
+                sender_id: "42".to_owned(),
+                ..letter("Ayanokouji", "Sakura Airi")
+            },
+            None,
+        )
+        .await
+        .unwrap();
+
+    let reply = send(&discord, &db, "Karuizawa Kei").await.unwrap();
+
+    assert_eq!(
+        reply,
+        "Thank you for your message, it has been recorded. It looks a lot like a letter \
+         you already sent, so a moderator will have a look at it."
+    );
+    let calls = discord.take();
+    let [Call::SendMessage { message, .. }] = calls.as_slice() else {
+        panic!("expected the letter to be logged, got {calls:?}");
+    };
+    let review = &message["embeds"][0]["fields"][3];
+    assert_eq!(review["name"], "⚠ Needs review");
+    assert_eq!(review["value"], format!("100% like their letter #{own}"));
+    assert_eq!(db.letters_sent_by("Ayanokouji".into()).await.unwrap(), 2);
+}
+
+#[tokio::test(start_paused = true)]
+async fn letters_that_look_encrypted_are_kept_as_written() {
+    let db = repository();
+    let discord = RecordingDiscord::default();
+    let looks_encrypted = format!("enc:v1:x:{LETTER}");
+    let interaction = command(
+        "sendletter",
+        42,
+        "Ayanokouji",
+        LETTERS,
+        &[
+            ("recipient", json!("Karuizawa Kei")),
+            ("letter", json!(looks_encrypted)),
+            ("anonymous", json!(true)),
+        ],
+    );
+    send_letter(Some(AUDIT))
+        .send(&interaction, &discord, &db)
+        .await
+        .unwrap();
+
+    // the next letter is still compared with it...
+    send(&discord, &db, "Sakura Airi").await.unwrap();
+    assert_eq!(db.letters_sent_by("Ayanokouji".into()).await.unwrap(), 2);
+    discord.take();
+
+    // ...and both are published as they were written
+    let interaction = command("publish", 2, "Chabashira", LETTERS, &[]);
+    publish::publish(&interaction, &discord, &db).await.unwrap();
+    let published = discord
+        .take()
+        .into_iter()
+        .filter_map(|call| match call {
+            Call::SendMessage { message, .. } => Some(message["embeds"][0]["description"].clone()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(published, [json!(looks_encrypted), json!(LETTER)]);
+}
+
+#[tokio::test]
+async fn only_members_of_the_guild_can_send_letters() {
+    let db = repository();
+    let discord = RecordingDiscord::default();
+    let sendletter = SendLetterCommand {
+        eligibility: Eligibility {
+            guild: Some(GuildId(GUILD)),
+            ..Default::default()
+        },
+        ..send_letter(Some(AUDIT))
+    };
+    let interaction = command(
+        "sendletter",
+        42,
+        "Ayanokouji",
+        LETTERS,
+        &[
+            ("recipient", json!("Karuizawa Kei")),
+            ("letter", json!(LETTER)),
+            ("anonymous", json!(true)),
+        ],
+    );
+
+    let refused = sendletter.send(&interaction, &discord, &db).await;
+    assert!(matches!(
+        refused,
+        Err(CommandError::User(message)) if message == "Only members of the server can send letters."
+    ));
+    assert_eq!(
+        discord.take(),
+        vec![Call::GetMember {
+            guild: GuildId(GUILD),
+            user: UserId(42),
+        }]
+    );
+    assert_eq!(db.letters_sent_by("Ayanokouji".into()).await.unwrap(), 0);
+
+    discord.add_member(member(
+        42,
+        "Ayanokouji",
+        "2023-02-01T00:00:00.000000+00:00",
+        &[],
+    ));
+    sendletter.send(&interaction, &discord, &db).await.unwrap();
+    assert_eq!(db.letters_sent_by("Ayanokouji".into()).await.unwrap(), 1);
+}
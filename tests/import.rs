@@ -0,0 +1,160 @@
+use cotevalentines::export::load_letters;
+use cotevalentines::import::{import, ImportError, ImportFormat};
+use cotevalentines::model::NO_SENDER_ID;
+use cotevalentines::repository::LetterRepository;
+use cotevalentines::votes;
+use serde_json::json;
+
+mod common;
+
+use common::{repository, LETTER};
+
+/// A message logged at 2023-02-14 14:00 UTC.
+const LOGGED_AT_TWO: u64 = 1_075_053_802_291_200_000;
+
+#[tokio::test]
+async fn rows_that_break_the_rules_are_skipped_and_reported() {
+    let db = repository();
+    let csv = format!(
+        "recipient,sender,sender_id,anon,content,created_at\n\
+         Karuizawa Kei,Ayanokouji,42,true,\"{LETTER}\",\n\
+         Karuizawa Kei,Ayanokouji,42,true,Too short,\n\
+         Karuizawa Kei,Ayanokouji,,true,\"{LETTER}\",\n\
+         Karuizawa Kei,Ayanokouji,42,true,\"{LETTER}\",yesterday\n"
+    );
+
+    let report = db
+        .run(move |conn| import(conn, csv.as_bytes(), ImportFormat::Csv, false))
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(report.letters, 2);
+    assert_eq!(report.recipients, 1);
+    let errors = report
+        .errors
+        .iter()
+        .map(|error| (error.row, error.message.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        errors[0],
+        (2, "Letter must be at least 100 characters long")
+    );
+    assert_eq!(errors[1].0, 4);
+    assert!(errors[1].1.starts_with("Invalid created_at \"yesterday\""));
+    assert!(report
+        .summary(1)
+        .starts_with("Imported 2 letters and 1 new recipients, skipped 2 rows:\nRow 2:"));
+    assert!(report.summary(1).ends_with("...and 1 more"));
+}
+
+#[tokio::test]
+async fn letters_without_a_sender_id_count_towards_no_quota() {
+    let db = repository();
+    let csv = format!(
+        "recipient,sender,anon,content\n\
+         Karuizawa Kei,Ayanokouji,true,\"{LETTER}\"\n\
+         Karuizawa Kei,Ayanokouji,true,\"{LETTER}\"\n"
+    );
+
+    let report = db
+        .run(move |conn| import(conn, csv.as_bytes(), ImportFormat::Csv, false))
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(report.letters, 2);
+    let imported = db.query(load_letters).await.unwrap();
+    assert!(imported
+        .iter()
+        .all(|letter| letter.sender_id == NO_SENDER_ID));
+    assert_eq!(db.letters_sent_by("Ayanokouji".into()).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn files_that_cannot_be_parsed_are_not_database_errors() {
+    let db = repository();
+
+    let imported = db
+        .run(|conn| import(conn, b"not json", ImportFormat::Json, false))
+        .await
+        .unwrap();
+
+    assert!(
+        matches!(imported, Err(ImportError::Parse(message)) if message.starts_with("Invalid JSON"))
+    );
+}
+
+#[tokio::test]
+async fn dry_runs_only_check_the_file() {
+    let db = repository();
+    let rows = json!([{
+        "recipient": "Karuizawa Kei",
+        "sender": "Ayanokouji",
+        "sender_id": "42",
+        "anonymous": true,
+        "letter": LETTER,
+    }])
+    .to_string();
+
+    let report = db
+        .run(move |conn| import(conn, rows.as_bytes(), ImportFormat::Json, true))
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(
+        report.summary(10),
+        "Would import 1 letters and 1 new recipients"
+    );
+    assert_eq!(db.letters_sent_by("Ayanokouji".into()).await.unwrap(), 0);
+    db.reload_recipients().await.unwrap();
+    assert!(db.search_recipients("Kei").is_empty());
+}
+
+#[tokio::test]
+async fn imported_letters_keep_their_place_in_the_send_order() {
+    let db = repository();
+    let mut live = common::letter("Kushida", "Horikita Suzune");
+    live.sent_at = "2023-02-14T13:00:00".parse().unwrap();
+    let live = db.add_letter(live, None).await.unwrap();
+
+    let rows = json!([
+        {
+            "recipient": "Karuizawa Kei",
+            "sender": "Ayanokouji",
+            "sender_id": "42",
+            "anon": false,
+            "content": LETTER,
+            "message_id": LOGGED_AT_TWO.to_string(),
+        },
+        {
+            "recipient": "Karuizawa Kei",
+            "sender": "Ayanokouji",
+            "sender_id": "42",
+            "anon": false,
+            "content": LETTER,
+            "created_at": "2023-02-14T12:00:00.000Z",
+        },
+    ])
+    .to_string();
+    db.run(move |conn| import(conn, rows.as_bytes(), ImportFormat::Json, false))
+        .await
+        .unwrap()
+        .unwrap();
+
+    let ranked = db.query(votes::shortlist).await.unwrap();
+    let sent = ranked
+        .iter()
+        .map(|(letter, _)| letter.created_at.unwrap().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        sent,
+        [
+            "2023-02-14 12:00:00",
+            "2023-02-14 13:00:00",
+            "2023-02-14 14:00:00"
+        ]
+    );
+    assert_eq!(ranked[1].0.id, live);
+}
@@ -0,0 +1,148 @@
+use cotevalentines::commands::{registry, stats::Dashboard};
+use cotevalentines::cooldown::Cooldown;
+use cotevalentines::eligibility::Eligibility;
+use cotevalentines::filter::ContentFilter;
+use cotevalentines::permissions::{Access, Level, ModAction};
+use serde_json::json;
+use serenity::model::guild::Member;
+use serenity::model::id::RoleId;
+
+const MODERATOR_ROLE: u64 = 700;
+
+/// A member with these roles and permissions, as a bit set.
+fn member(roles: &[u64], permissions: u64) -> Member {
+    serde_json::from_value(json!({
+        "user": {
+            "id": "42",
+            "username": "Ayanokouji",
+            "discriminator": "0001",
+            "avatar": null,
+        },
+        "guild_id": "500",
+        "roles": roles.iter().map(u64::to_string).collect::<Vec<_>>(),
+        "joined_at": "2023-02-01T00:00:00.000000+00:00",
+        "deaf": false,
+        "mute": false,
+        "permissions": permissions.to_string(),
+    }))
+    .expect("Could not build member")
+}
+
+const MANAGE_MESSAGES: u64 = 1 << 13;
+const MANAGE_GUILD: u64 = 1 << 5;
+
+#[test]
+fn manage_messages_makes_a_moderator_without_a_role() {
+    let access = Access::default();
+
+    assert!(access
+        .check(Some(&member(&[], MANAGE_MESSAGES)), ModAction::Delete)
+        .is_ok());
+    assert_eq!(
+        access.check(Some(&member(&[], 0)), ModAction::Delete),
+        Err("You aren't allowed to do this. (Manage Messages permission required)".to_owned())
+    );
+    assert!(access
+        .check(Some(&member(&[], MANAGE_MESSAGES)), ModAction::Import)
+        .is_err());
+}
+
+#[test]
+fn the_moderator_role_replaces_manage_messages() {
+    let access = Access {
+        moderator_role: Some(RoleId(MODERATOR_ROLE)),
+        ..Default::default()
+    };
+
+    assert!(access
+        .check(Some(&member(&[MODERATOR_ROLE], 0)), ModAction::Publish)
+        .is_ok());
+    assert_eq!(
+        access.check(Some(&member(&[], MANAGE_MESSAGES)), ModAction::Publish),
+        Err("You aren't allowed to do this. (event moderator role required)".to_owned())
+    );
+    // admins can do what moderators can
+    assert!(access
+        .check(Some(&member(&[], MANAGE_GUILD)), ModAction::Publish)
+        .is_ok());
+    assert!(access
+        .check(Some(&member(&[MODERATOR_ROLE], 0)), ModAction::AuditLog)
+        .is_err());
+}
+
+#[test]
+fn levels_can_be_changed_per_action() {
+    let access = Access {
+        moderator_role: Some(RoleId(MODERATOR_ROLE)),
+        levels: serde_json::from_value(json!({
+            "delete": "admin",
+            "recipients": "moderator",
+        }))
+        .unwrap(),
+    };
+    let moderator = member(&[MODERATOR_ROLE], 0);
+
+    assert_eq!(access.level(ModAction::Delete), Level::Admin);
+    assert!(access.check(Some(&moderator), ModAction::Delete).is_err());
+    assert!(access
+        .check(Some(&moderator), ModAction::Recipients)
+        .is_ok());
+    assert!(access.check(Some(&moderator), ModAction::Review).is_ok());
+}
+
+#[test]
+fn unknown_actions_are_refused() {
+    assert!(
+        serde_json::from_value::<std::collections::HashMap<ModAction, Level>>(json!({
+            "approve": "moderator",
+        }))
+        .is_err()
+    );
+}
+
+#[test]
+fn nothing_is_allowed_outside_the_server() {
+    assert!(Access::default().check(None, ModAction::Review).is_err());
+}
+
+#[test]
+fn only_sendletter_is_for_everyone() {
+    let registry = registry(
+        Dashboard::default(),
+        ContentFilter::default(),
+        Cooldown::default(),
+        Eligibility::default(),
+    );
+    let sendletter = registry.command("sendletter").unwrap();
+
+    assert_eq!(sendletter.action(), None);
+    assert_eq!(
+        sendletter.component_action("delete_letter"),
+        Some(ModAction::Delete)
+    );
+    for name in [
+        "publish",
+        "add_recipient",
+        "allow_letters",
+        "import",
+        "export",
+        "reading_order",
+        "podcast_script",
+        "shortlist",
+        "card",
+        "stats",
+        "block",
+        "unblock",
+        "auditlog",
+    ] {
+        let command = registry.command(name).unwrap();
+        assert!(command.action().is_some(), "/{name} is open to everyone");
+    }
+    assert_eq!(
+        registry
+            .owner_of("block_modal")
+            .unwrap()
+            .component_action("block_modal"),
+        Some(ModAction::Block)
+    );
+}
@@ -0,0 +1,138 @@
+use cotevalentines::commands::send::ValentineLetter;
+use cotevalentines::model::Letter;
+use cotevalentines::podcast::{self, load_featured, move_letter, read_time, script};
+use cotevalentines::repository::{LetterRepository, Repository};
+
+mod common;
+
+use common::{letter, repository, with_letters, LETTER};
+
+async fn feature(db: &Repository, message: &'static str) -> Letter {
+    db.query(move |conn| podcast::toggle_featured(conn, message))
+        .await
+        .unwrap()
+}
+
+fn order(letters: &[Letter]) -> Vec<(&str, Option<i32>)> {
+    letters
+        .iter()
+        .map(|letter| (letter.sender.as_str(), letter.reading_order))
+        .collect()
+}
+
+#[tokio::test]
+async fn featured_letters_are_read_in_the_order_they_were_featured() {
+    let db = with_letters(
+        ["Ayanokouji", "Ryuuen", "Kushida"].map(|sender| letter(sender, "Karuizawa Kei")),
+    )
+    .await;
+
+    feature(&db, "102").await;
+    feature(&db, "100").await;
+    feature(&db, "101").await;
+
+    let featured = db.query(load_featured).await.unwrap();
+    assert_eq!(
+        order(&featured),
+        [
+            ("Kushida", Some(1)),
+            ("Ayanokouji", Some(2)),
+            ("Ryuuen", Some(3))
+        ]
+    );
+}
+
+#[tokio::test]
+async fn the_reading_order_has_no_gaps() {
+    let db = with_letters(
+        ["Ayanokouji", "Ryuuen", "Kushida"].map(|sender| letter(sender, "Karuizawa Kei")),
+    )
+    .await;
+    for message in ["100", "101", "102"] {
+        feature(&db, message).await;
+    }
+
+    // taking a letter off the show closes the gap it leaves...
+    let removed = feature(&db, "100").await;
+    assert!(!removed.featured);
+    assert_eq!(removed.reading_order, None);
+    let featured = db.query(load_featured).await.unwrap();
+    assert_eq!(
+        order(&featured),
+        [("Ryuuen", Some(1)), ("Kushida", Some(2))]
+    );
+
+    // ...featuring it again puts it last...
+    feature(&db, "100").await;
+
+    // ...and moving one shifts the others
+    let kushida = featured[1].id;
+    let moved = db
+        .query(move |conn| move_letter(conn, kushida, 1))
+        .await
+        .unwrap();
+    assert_eq!(
+        order(&moved),
+        [
+            ("Kushida", Some(1)),
+            ("Ryuuen", Some(2)),
+            ("Ayanokouji", Some(3))
+        ]
+    );
+
+    // positions past the end move letters to the end
+    let moved = db
+        .query(move |conn| move_letter(conn, kushida, 10))
+        .await
+        .unwrap();
+    assert_eq!(
+        order(&moved),
+        [
+            ("Ryuuen", Some(1)),
+            ("Ayanokouji", Some(2)),
+            ("Kushida", Some(3))
+        ]
+    );
+}
+
+#[tokio::test]
+async fn scripts_keep_anonymous_senders_off_the_air() {
+    let db = repository();
+    db.add_letter(letter("Ayanokouji", "Karuizawa Kei"), Some("100".into()))
+        .await
+        .unwrap();
+    db.add_letter(
+        ValentineLetter {
+            anon: true,
+            ..letter("Kushida", "Horikita Suzune")
+        },
+        Some("101".into()),
+    )
+    .await
+    .unwrap();
+    feature(&db, "101").await;
+    feature(&db, "100").await;
+
+    let script = script(&db.query(load_featured).await.unwrap());
+
+    let words = LETTER.split_whitespace().count();
+    assert!(script.starts_with(&format!(
+        "# Podcast of the Elite: Valentine's letters\n\n2 letters, {} words, about {} of reading.\n",
+        words * 2,
+        read_time(words * 2)
+    )));
+    assert!(script.contains(&format!(
+        "## To Horikita Suzune\n\n### 1. From Anonymous (letter #2)\n\n*{words} words, about {}*\n\n{LETTER}\n",
+        read_time(words)
+    )));
+    assert!(script.contains("## To Karuizawa Kei\n\n### 2. From Ayanokouji (letter #1)\n"));
+    assert!(!script.contains("Kushida"));
+}
+
+#[test]
+fn read_times_are_rounded_up_to_the_second() {
+    assert_eq!(read_time(0), "0 s");
+    assert_eq!(read_time(1), "1 s");
+    assert_eq!(read_time(150), "1 min");
+    assert_eq!(read_time(200), "1 min 20 s");
+}
@@ -0,0 +1,124 @@
+use cotevalentines::commands::registration::{diff, CommandDiff};
+use cotevalentines::commands::{registry, stats::Dashboard};
+use cotevalentines::cooldown::Cooldown;
+use cotevalentines::eligibility::Eligibility;
+use cotevalentines::filter::ContentFilter;
+use cotevalentines::permissions::{Access, Level, ModAction};
+use serde_json::{json, Value};
+use serenity::builder::CreateApplicationCommands;
+use serenity::model::application::command::Command;
+use serenity::model::id::RoleId;
+
+fn wanted() -> Vec<Value> {
+    registered_with(&Access::default())
+}
+
+fn registered_with(access: &Access) -> Vec<Value> {
+    let mut commands = CreateApplicationCommands::default();
+    registry(
+        Dashboard::default(),
+        ContentFilter::default(),
+        Cooldown::default(),
+        Eligibility::default(),
+    )
+    .register_all(&mut commands, access);
+    commands.0
+}
+
+/// A command the way Discord returns it once registered, with every field
+/// filled in.
+fn as_registered(command: &Value) -> Value {
+    let mut command = command.clone();
+    let fields = command.as_object_mut().unwrap();
+    fields.insert("id".into(), json!("1"));
+    fields.insert("application_id".into(), json!("2"));
+    fields.insert("version".into(), json!("3"));
+    fields.entry("type").or_insert(json!(1));
+    let command: Command = serde_json::from_value(command).unwrap();
+    serde_json::to_value(command).unwrap()
+}
+
+fn names(commands: &[Value]) -> Vec<String> {
+    commands
+        .iter()
+        .map(|command| command["name"].as_str().unwrap().to_owned())
+        .collect()
+}
+
+#[test]
+fn everything_is_added_at_first() {
+    let wanted = wanted();
+
+    assert_eq!(
+        diff(&[], &wanted),
+        CommandDiff {
+            added: names(&wanted),
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn registered_commands_are_up_to_date() {
+    let wanted = wanted();
+    let registered = wanted.iter().map(as_registered).collect::<Vec<_>>();
+
+    assert!(diff(&registered, &wanted).is_empty());
+}
+
+#[test]
+fn changed_and_stale_commands_are_found() {
+    let wanted = wanted();
+    let mut registered = wanted.iter().map(as_registered).collect::<Vec<_>>();
+    registered[0]["description"] = json!("An older description");
+    registered[1]["dm_permission"] = json!(true);
+    registered.push(as_registered(&json!({
+        "name": "letters",
+        "description": "A command that was renamed since",
+        "type": 1,
+    })));
+    let added = registered.remove(2);
+
+    assert_eq!(
+        diff(&registered, &wanted),
+        CommandDiff {
+            added: names(&[added]),
+            removed: vec!["letters".to_owned()],
+            changed: names(&wanted[..2]),
+        }
+    );
+}
+
+/// The permissions Discord requires before showing the command called `name`.
+fn permissions(commands: &[Value], name: &str) -> Value {
+    let command = commands
+        .iter()
+        .find(|command| command["name"] == name)
+        .unwrap();
+    command
+        .get("default_member_permissions")
+        .cloned()
+        .unwrap_or_default()
+}
+
+#[test]
+fn moderator_commands_are_shown_to_everyone_with_a_moderator_role() {
+    let manage_messages = json!("8192");
+    let manage_guild = json!("32");
+    let send_messages = json!("2048");
+
+    let by_permission = wanted();
+    assert_eq!(permissions(&by_permission, "publish"), manage_messages);
+    assert_eq!(permissions(&by_permission, "import"), manage_guild);
+    assert_eq!(permissions(&by_permission, "sendletter"), send_messages);
+
+    let by_role = registered_with(&Access {
+        moderator_role: Some(RoleId(700)),
+        levels: [(ModAction::Export, Level::Admin)].into(),
+    });
+    assert_eq!(permissions(&by_role, "publish"), Value::Null);
+    assert_eq!(permissions(&by_role, "block"), Value::Null);
+    assert_eq!(permissions(&by_role, "export"), manage_guild);
+    assert_eq!(permissions(&by_role, "import"), manage_guild);
+    assert_eq!(permissions(&by_role, "sendletter"), send_messages);
+}
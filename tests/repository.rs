@@ -0,0 +1,231 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use cotevalentines::blocklist;
+use cotevalentines::commands::send::ValentineLetter;
+use cotevalentines::model::{BlockedUser, Letter, Recipient};
+use cotevalentines::repository::{parse_recipients, LetterRepository, LETTERS_PER_SENDER};
+use cotevalentines::schema::letters::dsl::{letters, message_id};
+use cotevalentines::{encryption, podcast, votes};
+use diesel::prelude::*;
+
+mod common;
+
+use common::{letter, repository, LETTER};
+
+fn recipient(name: &str) -> Recipient {
+    Recipient {
+        fullname: name.to_owned(),
+        is_real: true,
+    }
+}
+
+#[tokio::test]
+async fn quota_counts_letters_per_sender() {
+    let db = repository();
+
+    for sent in 0..LETTERS_PER_SENDER {
+        assert_eq!(db.letters_sent_by("Ayanokouji".into()).await.unwrap(), sent);
+        assert!(db.can_send_letter("Ayanokouji".into()).await.unwrap());
+        db.add_letter(letter("Ayanokouji", "Karuizawa Kei"), None)
+            .await
+            .unwrap();
+    }
+
+    assert!(!db.can_send_letter("Ayanokouji".into()).await.unwrap());
+    assert!(db.can_send_letter("Horikita".into()).await.unwrap());
+}
+
+#[tokio::test]
+async fn add_letter_stores_every_field() {
+    let db = repository();
+
+    let first = db
+        .add_letter(letter("Kushida", "Horikita Suzune"), Some("100".into()))
+        .await
+        .unwrap();
+    let second = db
+        .add_letter(letter("Ichinose", "Kanzaki"), Some("101".into()))
+        .await
+        .unwrap();
+    assert_ne!(first, second);
+
+    let stored = db.delete_letter("100".into()).await.unwrap().unwrap();
+    assert_eq!(stored.id, first);
+    assert_eq!(stored.sender, "Kushida");
+    assert_eq!(stored.sender_id, "Kushida#id");
+    assert_eq!(stored.recipient, "Horikita Suzune");
+    assert_eq!(stored.content, LETTER);
+    assert_eq!(stored.message_id.as_deref(), Some("100"));
+    assert!(!stored.anon);
+    assert!(!stored.featured);
+    assert_eq!(stored.reading_order, None);
+    assert_eq!(
+        stored.created_at.unwrap().to_string(),
+        "2023-02-14 12:00:00"
+    );
+    assert_eq!(stored.updated_at, stored.created_at);
+    assert_eq!(stored.guild_id, Some(common::GUILD.to_string()));
+    assert_eq!(stored.channel_id.as_deref(), Some("301"));
+}
+
+#[tokio::test]
+async fn delete_letter_by_audit_message() {
+    let db = repository();
+
+    db.add_letter(letter("Ryuuen", "Ibuki"), Some("200".into()))
+        .await
+        .unwrap();
+    db.add_letter(letter("Ryuuen", "Karuizawa Kei"), Some("201".into()))
+        .await
+        .unwrap();
+    db.run(|conn| votes::cast_vote(conn, "200", "moderator", 1))
+        .await
+        .unwrap()
+        .unwrap();
+
+    let deleted = db.delete_letter("200".into()).await.unwrap().unwrap();
+    assert_eq!(deleted.recipient, "Ibuki");
+    assert!(db.delete_letter("200".into()).await.unwrap().is_none());
+    assert!(db.delete_letter("999".into()).await.unwrap().is_none());
+
+    // deleting frees up quota, and takes the letter's votes with it
+    assert_eq!(db.letters_sent_by("Ryuuen".into()).await.unwrap(), 1);
+    let ranked = db.query(votes::shortlist).await.unwrap();
+    assert_eq!(ranked.len(), 1);
+    assert_eq!(ranked[0].0.recipient, "Karuizawa Kei");
+    assert_eq!(ranked[0].1.votes, 0);
+}
+
+#[tokio::test]
+async fn search_recipients_ignores_case() {
+    let db = repository();
+
+    for name in ["Horikita Suzune", "Horikita Manabu", "Karuizawa Kei"] {
+        db.add_recipient(recipient(name)).await.unwrap();
+    }
+
+    assert_eq!(
+        db.search_recipients("horikita"),
+        ["Horikita Suzune", "Horikita Manabu"]
+    );
+    assert_eq!(db.search_recipients("KEI"), ["Karuizawa Kei"]);
+    assert_eq!(db.search_recipients("").len(), 3);
+    assert!(db.search_recipients("Sakayanagi").is_empty());
+}
+
+#[tokio::test]
+async fn search_recipients_returns_at_most_25() {
+    let db = repository();
+
+    for i in 0..30 {
+        db.add_recipient(recipient(&format!("Student {i}")))
+            .await
+            .unwrap();
+    }
+
+    assert_eq!(db.search_recipients("student").len(), 25);
+}
+
+#[tokio::test]
+async fn seed_recipients_replaces_existing_ones() {
+    let db = repository();
+
+    db.add_recipient(recipient("Sakayanagi Arisu"))
+        .await
+        .unwrap();
+    db.seed_recipients(parse_recipients("Chabashira_Sae:Hoshinomiya_Chie"))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        db.search_recipients(""),
+        ["Chabashira Sae", "Hoshinomiya Chie"]
+    );
+
+    // check what was stored, not just the cache
+    db.reload_recipients().await.unwrap();
+    assert!(db.search_recipients("Sakayanagi").is_empty());
+    assert_eq!(db.search_recipients("sae"), ["Chabashira Sae"]);
+}
+
+#[tokio::test]
+async fn blocking_again_replaces_the_reason() {
+    let db = repository();
+    let block = |reason: &str| {
+        let blocked = BlockedUser {
+            user_id: "42".to_owned(),
+            reason: reason.to_owned(),
+            blocked_by: "2".to_owned(),
+        };
+        db.query(move |conn| blocklist::block(conn, &blocked))
+    };
+
+    block("Spam").await.unwrap();
+    block("Harassment").await.unwrap();
+    assert!(db.is_blocked("42".into()).await.unwrap());
+    assert!(!db.is_blocked("43".into()).await.unwrap());
+
+    let reasons: Vec<String> = db
+        .query(|conn| {
+            use cotevalentines::schema::blocked_users::dsl::{blocked_users, reason};
+            use diesel::prelude::*;
+            blocked_users.select(reason).load(conn)
+        })
+        .await
+        .unwrap();
+    assert_eq!(reasons, ["Harassment"]);
+
+    assert!(db
+        .query(|conn| blocklist::unblock(conn, "42"))
+        .await
+        .unwrap());
+    assert!(!db.is_blocked("42".into()).await.unwrap());
+    assert!(!db
+        .query(|conn| blocklist::unblock(conn, "42"))
+        .await
+        .unwrap());
+}
+
+#[tokio::test]
+async fn updating_a_letter_changes_updated_at() {
+    let db = repository();
+    let now = Utc::now().naive_utc();
+    db.add_letter(
+        ValentineLetter {
+            sent_at: now,
+            ..letter("Kushida", "Horikita Suzune")
+        },
+        Some("100".into()),
+    )
+    .await
+    .unwrap();
+    let updated_at = |letter: &Letter| letter.updated_at.unwrap();
+
+    let sent = db
+        .query(|conn| letters.filter(message_id.eq("100")).first::<Letter>(conn))
+        .await
+        .unwrap();
+    assert_eq!(updated_at(&sent), now);
+
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    let featured = db
+        .query(|conn| podcast::toggle_featured(conn, "100"))
+        .await
+        .unwrap();
+    assert!(updated_at(&featured) > updated_at(&sent));
+
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    db.query(encryption::rotate).await.unwrap();
+    let rotated = db.query(podcast::load_featured).await.unwrap();
+    assert_eq!(rotated[0].created_at, Some(now));
+    assert!(updated_at(&rotated[0]) > updated_at(&featured));
+}
+
+#[test]
+fn parse_recipients_splits_names() {
+    assert_eq!(
+        parse_recipients("Ayanokouji_Kiyotaka:Ibuki"),
+        ["Ayanokouji Kiyotaka", "Ibuki"]
+    );
+}
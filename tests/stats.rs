@@ -0,0 +1,91 @@
+use chrono::NaiveDate;
+use cotevalentines::commands::send::ValentineLetter;
+use cotevalentines::commands::stats::load_dashboard;
+use cotevalentines::model::Recipient;
+use cotevalentines::repository::LetterRepository;
+use cotevalentines::stats::{self, collect};
+use serenity::model::id::{ChannelId, MessageId};
+
+mod common;
+
+use common::{letter, repository};
+
+#[tokio::test]
+async fn letters_are_counted_per_recipient_and_day() {
+    let db = repository();
+    db.add_recipient(Recipient {
+        fullname: "Karuizawa Kei".to_owned(),
+        is_real: false,
+    })
+    .await
+    .unwrap();
+    db.add_recipient(Recipient {
+        fullname: "Chabashira".to_owned(),
+        is_real: true,
+    })
+    .await
+    .unwrap();
+    let next_day = NaiveDate::from_ymd_opt(2023, 2, 15)
+        .unwrap()
+        .and_hms_opt(9, 0, 0)
+        .unwrap();
+    for sent in [
+        ValentineLetter {
+            anon: true,
+            ..letter("Ayanokouji", "Karuizawa Kei")
+        },
+        letter("Ryuuen", "Karuizawa Kei"),
+        ValentineLetter {
+            sent_at: next_day,
+            ..letter("Ryuuen", "Chabashira")
+        },
+        letter("Kushida", "Horikita Suzune"),
+    ] {
+        db.add_letter(sent, None).await.unwrap();
+    }
+
+    let stats = db.query(collect).await.unwrap();
+
+    assert_eq!(stats.letters, 4);
+    assert_eq!(stats.anonymous, 1);
+    assert_eq!(stats.anonymous_percentage(), 25.0);
+    assert_eq!(stats.unique_senders, 3);
+    assert_eq!(
+        stats.per_recipient,
+        [
+            ("Karuizawa Kei".to_owned(), 2),
+            ("Chabashira".to_owned(), 1),
+            ("Horikita Suzune".to_owned(), 1),
+        ]
+    );
+    assert_eq!(
+        (stats.to_real, stats.to_fictional, stats.to_unlisted),
+        (1, 2, 1)
+    );
+    assert_eq!(
+        stats.per_day,
+        [
+            (NaiveDate::from_ymd_opt(2023, 2, 14).unwrap(), 3),
+            (next_day.date(), 1),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn the_pinned_dashboard_is_remembered() {
+    let db = repository();
+    assert_eq!(*load_dashboard(&db).await.unwrap().lock().unwrap(), None);
+
+    db.query(|conn| stats::save_dashboard(conn, "300", "1000"))
+        .await
+        .unwrap();
+    db.query(|conn| stats::save_dashboard(conn, "300", "1001"))
+        .await
+        .unwrap();
+
+    let dashboard = load_dashboard(&db).await.unwrap();
+    assert_eq!(
+        *dashboard.lock().unwrap(),
+        Some((ChannelId(300), MessageId(1001)))
+    );
+}
@@ -0,0 +1,73 @@
+use chrono::NaiveDateTime;
+use cotevalentines::commands::send::ValentineLetter;
+use cotevalentines::repository::Repository;
+use cotevalentines::votes::{self, Score};
+
+mod common;
+
+use common::{letter, with_letters};
+
+async fn vote(
+    db: &Repository,
+    message: &'static str,
+    moderator: &'static str,
+    value: i32,
+) -> String {
+    let (_, score) = db
+        .query(move |conn| votes::cast_vote(conn, message, moderator, value))
+        .await
+        .unwrap();
+    score.to_string()
+}
+
+#[tokio::test]
+async fn votes_are_tallied_once_per_moderator() {
+    let db = with_letters([letter("Ayanokouji", "Karuizawa Kei")]).await;
+
+    assert_eq!(vote(&db, "100", "Chabashira", 1).await, "+1 (1 vote)");
+    assert_eq!(vote(&db, "100", "Mashima", 1).await, "+2 (2 votes)");
+    // changing a vote replaces it...
+    assert_eq!(vote(&db, "100", "Mashima", -1).await, "+0 (2 votes)");
+    // ...and voting the same way again takes it back
+    assert_eq!(vote(&db, "100", "Mashima", -1).await, "+1 (1 vote)");
+    assert_eq!(vote(&db, "100", "Chabashira", 1).await, "+0 (0 votes)");
+}
+
+#[tokio::test]
+async fn the_shortlist_is_ranked_by_score_then_age() {
+    // Ichinose's letter was sent before Kushida's, but logged after it
+    let sent = |sender: &str, time: &str| ValentineLetter {
+        sent_at: format!("2023-02-14T{time}")
+            .parse::<NaiveDateTime>()
+            .unwrap(),
+        ..letter(sender, "Karuizawa Kei")
+    };
+    let db = with_letters([
+        sent("Ayanokouji", "09:00:00"),
+        sent("Ryuuen", "10:00:00"),
+        sent("Kushida", "12:00:00"),
+        sent("Ichinose", "11:00:00"),
+    ])
+    .await;
+    vote(&db, "101", "Chabashira", -1).await;
+    vote(&db, "102", "Chabashira", 1).await;
+    vote(&db, "102", "Mashima", 1).await;
+    vote(&db, "103", "Chabashira", 1).await;
+    vote(&db, "103", "Mashima", 1).await;
+
+    let ranked = db.query(votes::shortlist).await.unwrap();
+
+    let ranked = ranked
+        .iter()
+        .map(|(letter, Score { total, votes })| (letter.sender.as_str(), *total, *votes))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        ranked,
+        [
+            ("Ichinose", 2, 2),
+            ("Kushida", 2, 2),
+            ("Ayanokouji", 0, 0),
+            ("Ryuuen", -1, 1),
+        ]
+    );
+}